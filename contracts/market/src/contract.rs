@@ -3,17 +3,29 @@ use std::str::FromStr;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    Binary, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult, to_json_binary
+    Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo, Response, StdError,
+    StdResult, Storage, Timestamp, to_json_binary
 };
 use cw2::set_contract_version;
 
 /// TODO: Price per share at each buy and sell
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MarketType, MigrateMsg, PricingMode, QueryMsg, ResolutionMode,
+    TaxConfig,
+};
 use crate::state::{
-    Config, MarketOption, MarketState, MarketStatus, Share, CONFIG, MARKET_STATE, SHARES,
+    house_positions, Config, Dispute, FixedOddsBet, HouseLine, MarketOption, MarketState,
+    MarketStatus, OracleQuorum, Order, OrderSide, PriceSnapshot, ResolutionRecord, ResolverSet,
+    Share, AMM_POOLS, ASKS, BANKROLL, BARRIER_HIT, BETS, BIDS, COMPLETE_SETS, CONFIG, DISPUTE,
+    HOUSE_LINES, LEGACY_CONFIG, LEGACY_MARKET_STATE, LIABILITIES, LMSR_QUANTITIES, LMSR_SUBSIDY,
+    LP_SHARES, MANUAL_PRICES, MARKET_STATE, NEXT_BET_ID, NEXT_ORDER_ID, OPTION_TOTALS, ORDERS,
+    OUTCOME_VOTES, QUORUM_SNAPSHOTS, REFUND_RATES, RESOLUTION, SECOND_SETTLEMENT_PRICE,
+    SECOND_STRIKE_PRICE, SETTLEMENT_PRICE, SHARES, SHARE_TOTALS, STRIKE_PRICE, TWAP_OBSERVATIONS,
+    USER_ORDERS,
 };
-use cosmwasm_std::{CosmosMsg, Uint128};
+use cosmwasm_std::{CosmosMsg, Decimal256, Uint128};
+use cw_storage_plus::{Bound, Item, Map};
 
 //Coreum related imports
 use coreum_wasm_sdk::types::coreum::asset::ft::v1::MsgMint;
@@ -22,15 +34,40 @@ use coreum_wasm_sdk::types::coreum::asset::ft::v1::{MsgBurn, MsgIssue};
 use coreum_wasm_sdk::types::cosmos::bank::v1beta1::MsgSend;
 use coreum_wasm_sdk::types::cosmos::base::v1beta1::Coin;
 
-use cw_utils::must_pay;
-
-use clp_feed_interface::ClpFeedQuerier;
+use cw_utils::{must_pay, one_coin, PaymentError};
 
+use utils::error::ContractError as UtilsError;
+use utils::lmsr;
+use utils::math::median;
+use utils::oracle::{
+    ClpFeedOracle, FixedPriceOracle, OraclePrice, OracleSource, PriceOracle, WasmQueryOracle,
+};
+use utils::validation::{is_valid_subunit, is_valid_symbol};
 
 // Contract name and version for migration
 const CONTRACT_NAME: &str = "crates.io:cruise-control-prediction-market";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Time given to resolve a market after end_time before it can be voided (7 days)
+pub const DEFAULT_RESOLUTION_TIMEOUT: u64 = 7 * 24 * 3600;
+
+// Most options a single market can have (one FT is issued per option)
+pub const MAX_OPTIONS: usize = 10;
+
+// Furthest the strike price of an UpDown or Relative market may be from start_time, in seconds
+pub const STRIKE_PRICE_TOLERANCE: u64 = 300;
+
+// Most TWAP observations kept per asset in the window. The window is split in this many
+// equal buckets, each keeping its latest observation
+pub const MAX_TWAP_OBSERVATIONS: usize = 100;
+
+// Most open orders a new order fills in one transaction, the rest of it stays open
+pub const MAX_ORDER_FILLS: usize = 30;
+
+// Price levels returned per side of the order book
+const DEFAULT_BOOK_DEPTH: u32 = 20;
+const MAX_BOOK_DEPTH: u32 = 100;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -38,115 +75,222 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    //NOTES: Each market will cost at least 20 COREUM to create (2 FT tokens creates)
+    //NOTES: Each market will cost at least 10 COREUM per option to create (one FT token per option)
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     // Get the options for this market type
     let options = msg.market_type.get_options();
 
-    // Get initial price from clp_feed contract
-
-    let oracle = ClpFeedQuerier::new(&deps.querier, msg.oracle.clone());
-    let initial_price = oracle.query_price(msg.asset_to_track.clone())?;
-    if initial_price.price.is_none() {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Failed to get initial price from clp_feed contract",
-        )));
+    // Prices come from the oracle adapter selected by the resolution source
+    let oracle_source = OracleSource::from_str(&msg.resolution_source)?;
+    match &oracle_source {
+        OracleSource::WasmQuery(source) => source.validate()?,
+        // Manual prices start at the fixed price for every tracked asset
+        OracleSource::Fixed { price } => {
+            for asset in [Some(&msg.asset_to_track), msg.second_asset_to_track.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                MANUAL_PRICES.save(
+                    deps.storage,
+                    asset,
+                    &PriceSnapshot {
+                        price: *price,
+                        timestamp: env.block.time,
+                    },
+                )?;
+            }
+        }
+        OracleSource::ClpFeed => {}
     }
 
+    // Event markets are resolved by their resolvers and don't track a price
+    let initial_price = if msg.market_type.is_price_based() {
+        let initial_price =
+            query_source_price(deps.as_ref(), &oracle_source, &msg.oracle, &msg.asset_to_track)?;
+        Some(initial_price.ok_or_else(|| {
+            ContractError::Std(StdError::generic_err("Failed to get initial price from oracle"))
+        })?)
+    } else {
+        None
+    };
 
+    // Event markets default to the admin as their only resolver
+    let resolvers = match (&msg.market_type, msg.resolvers.clone()) {
+        (_, Some(resolvers)) => {
+            let mut members = resolvers.members.clone();
+            members.sort();
+            members.dedup();
+            if resolvers.threshold == 0
+                || members.len() != resolvers.members.len()
+                || resolvers.threshold as usize > members.len()
+            {
+                return Err(ContractError::InvalidResolverSet {});
+            }
+            Some(resolvers)
+        }
+        (MarketType::Event { .. }, None) => Some(ResolverSet {
+            members: vec![msg.admin.clone()],
+            threshold: 1,
+        }),
+        _ => None,
+    };
 
-    let subunit_token_a = format!(
-        "truth{}_{}",
-        options[0].to_lowercase().replace(" ", "_"),
-        msg.id.to_lowercase().replace(" ", "_")
-    );
-
-    let symbol_token_a = format!(
-        "TM{}{}", // TM prefix for "Truth Markets"
-        options[0].replace(" ", ""),
-        msg.id.replace(" ", "")
-    );
-
-    // Issue two new smart tokens for the market options
-    let issue_token_a = MsgIssue {
-        issuer: env.contract.address.to_string(),
-        symbol: symbol_token_a.clone(),
-        subunit: subunit_token_a.clone(),
-        precision: 6,
-        initial_amount: "0".to_string(),
-        description: format!("Token for {} in market {}", options[0], msg.id),
-        //Minting & Burning is enabled
-        features: vec![0 as i32, 1 as i32],
-        burn_rate: "0".to_string(),
-        send_commission_rate: "0".to_string(),
-        uri: "https://app.cruise-control.xyz/dashboard".to_string(),
-        uri_hash: "".to_string(),
-        extension_settings: None,
-        dex_settings: None,
-    };
-
-    let denom_token_a: String = format!("{}-{}", subunit_token_a, env.contract.address);
-
-    let subunit_token_b = format!(
-        "truth{}_{}",
-        options[1].to_lowercase().replace(" ", "_"),
-        msg.id.to_lowercase().replace(" ", "_")
-    );
-
-    let symbol_token_b = format!(
-        "TM{}{}", // TM prefix for "Truth Markets"
-        options[1].replace(" ", ""),
-        msg.id.replace(" ", "")
-    );
-
-    let issue_token_b = MsgIssue {
-        issuer: env.contract.address.to_string(),
-        symbol: symbol_token_b.clone(),
-        subunit: subunit_token_b.clone(),
-        precision: 6,
-        initial_amount: "0".to_string(),
-        description: format!("Token for {} in market {}", options[1], msg.id),
-        features: vec![0 as i32, 1 as i32],
-        burn_rate: "0".to_string(),
-        send_commission_rate: "0".to_string(),
-        uri: "https://app.cruise-control.xyz".to_string(),
-        uri_hash: "".to_string(),
-        extension_settings: None,
-        dex_settings: None,
-    };
-
-    let denom_token_b = format!("{}-{}", subunit_token_b, env.contract.address);
-
-    // Create MarketOption structs with associated token denoms
-    let option_a = MarketOption {
-        text: options[0].clone(),
-        associated_token_denom: denom_token_a.clone(),
-    };
-
-    let option_b = MarketOption {
-        text: options[1].clone(),
-        associated_token_denom: denom_token_b.clone(),
+    // Relative markets compare asset_to_track against a second, different asset
+    let second_initial_price = match (&msg.market_type, &msg.second_asset_to_track) {
+        (MarketType::Relative, Some(second_asset)) if *second_asset != msg.asset_to_track => {
+            let price =
+                query_source_price(deps.as_ref(), &oracle_source, &msg.oracle, second_asset)?
+                    .ok_or_else(|| {
+                        ContractError::Std(StdError::generic_err(
+                            "Failed to get initial price of the second asset from oracle",
+                        ))
+                    })?;
+            Some(price)
+        }
+        (MarketType::Relative, _) => return Err(ContractError::InvalidSecondAsset {}),
+        _ => None,
     };
 
-    let market_state = MarketState {
+    if let Some(quorum) = &msg.oracle_quorum {
+        if quorum.oracles.is_empty()
+            || quorum.min_responses == 0
+            || quorum.min_responses as usize > quorum.oracles.len()
+        {
+            return Err(ContractError::InvalidOracleQuorum {});
+        }
+    }
+
+    if let Some(twap_window) = msg.twap_window {
+        if twap_window == 0
+            || msg.start_time.plus_seconds(twap_window) > msg.end_time
+            || msg.oracle_quorum.is_some()
+            || !msg.market_type.is_price_based()
+        {
+            return Err(ContractError::InvalidTwapWindow {});
+        }
+    }
+
+    match &msg.pricing_mode {
+        Some(PricingMode::Lmsr { liquidity }) if liquidity.is_zero() => {
+            return Err(ContractError::InvalidLiquidity {});
+        }
+        Some(PricingMode::FixedOdds) if matches!(msg.market_type, MarketType::Scalar { .. }) => {
+            return Err(ContractError::FixedOddsScalarMarket {});
+        }
+        _ => {}
+    }
+
+    let tax_config = msg.tax_config.clone().unwrap_or_default();
+    let market_length = msg
+        .end_time
+        .seconds()
+        .saturating_sub(msg.start_time.seconds());
+    if !tax_config.is_valid(market_length) {
+        return Err(ContractError::InvalidTaxConfig {});
+    }
+
+    if let MarketType::Range { boundaries } = &msg.market_type {
+        if boundaries.is_empty() || boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(ContractError::InvalidRangeBoundaries {});
+        }
+    }
+
+    if let MarketType::Scalar {
+        lower_bound,
+        upper_bound,
+    } = &msg.market_type
+    {
+        if lower_bound >= upper_bound {
+            return Err(ContractError::InvalidScalarBounds {});
+        }
+    }
+
+    if options.len() < 2 || options.len() > MAX_OPTIONS {
+        return Err(ContractError::InvalidOptionCount {
+            count: options.len(),
+            max: MAX_OPTIONS,
+        });
+    }
+
+    // Issue one new smart token for each market option
+    let mut pairs: Vec<MarketOption> = Vec::with_capacity(options.len());
+    let mut issue_msgs: Vec<CosmosMsg> = Vec::with_capacity(options.len());
+    for option in options.iter() {
+        if pairs.iter().any(|p| &p.text == option) {
+            return Err(ContractError::DuplicateOption {
+                option: option.clone(),
+            });
+        }
+
+        let subunit = format!(
+            "truth{}_{}",
+            option.to_lowercase().replace(" ", "_"),
+            msg.id.to_lowercase().replace(" ", "_")
+        );
+
+        let symbol = format!(
+            "TM{}{}", // TM prefix for "Truth Markets"
+            option.replace(" ", ""),
+            msg.id.replace(" ", "")
+        );
+
+        // The label becomes part of the token subunit and symbol, which Coreum restricts
+        if !is_valid_subunit(&subunit) || !is_valid_symbol(&symbol) {
+            return Err(ContractError::InvalidOptionLabel {
+                option: option.clone(),
+            });
+        }
+        // Labels differing only by case or spaces would issue the same token
+        let denom = format!("{}-{}", subunit, env.contract.address);
+        if pairs.iter().any(|p| p.associated_token_denom == denom) {
+            return Err(ContractError::DuplicateOption {
+                option: option.clone(),
+            });
+        }
+
+        let issue_token = MsgIssue {
+            issuer: env.contract.address.to_string(),
+            symbol,
+            subunit: subunit.clone(),
+            precision: 6,
+            initial_amount: "0".to_string(),
+            description: format!("Token for {} in market {}", option, msg.id),
+            //Minting & Burning is enabled
+            features: vec![0 as i32, 1 as i32],
+            burn_rate: "0".to_string(),
+            send_commission_rate: "0".to_string(),
+            uri: "https://app.cruise-control.xyz/dashboard".to_string(),
+            uri_hash: "".to_string(),
+            extension_settings: None,
+            dex_settings: None,
+        };
+        issue_msgs.push(CosmosMsg::Any(issue_token.to_any()));
+
+        // Create MarketOption structs with associated token denoms
+        pairs.push(MarketOption {
+            text: option.clone(),
+            associated_token_denom: denom,
+        });
+    }
+
+    let mut market_state = MarketState {
         status: MarketStatus::Pending,
         num_bettors: 0,
         total_value: Coin {
             denom: msg.buy_token.clone(),
             amount: "0".to_string(),
         },
-        total_stake_option_a: Uint128::zero(),
-        total_stake_option_b: Uint128::zero(),
         volume: Uint128::zero(),
+        bounty_reserve: Uint128::zero(),
     };
 
     let market_config = Config {
         id: msg.id.clone(),
         admin: msg.admin.clone(),
         commission_rate: msg.commission_rate.clone(),
-        pairs: vec![option_a, option_b],
+        pairs,
         start_time: msg.start_time.clone(),
         end_time: msg.end_time.clone(),
         buy_token: msg.buy_token.clone(),
@@ -156,12 +300,64 @@ pub fn instantiate(
         oracle: msg.oracle.clone(),
         resolution_source: msg.resolution_source.clone(),
         asset_to_track: msg.asset_to_track.clone(),
+        second_asset_to_track: second_initial_price
+            .as_ref()
+            .and(msg.second_asset_to_track.clone()),
         market_type: msg.market_type.clone(),
         target_price: msg.target_price.clone(),
         //TODO: check this
-        initial_price: Decimal::from_str(&initial_price.price.unwrap().price).unwrap(),
+        initial_price: initial_price
+            .as_ref()
+            .map(|price| price.price)
+            .unwrap_or_default(),
+        second_initial_price: second_initial_price.as_ref().map(|price| price.price),
+        resolution_deadline: msg
+            .end_time
+            .plus_seconds(msg.resolution_timeout.unwrap_or(DEFAULT_RESOLUTION_TIMEOUT)),
+        max_price_staleness: msg.max_price_staleness,
+        resolution_mode: msg.resolution_mode.clone(),
+        resolution_bounty: msg.resolution_bounty,
+        dispute_config: msg.dispute_config.clone(),
+        resolvers,
+        oracle_quorum: msg.oracle_quorum.clone(),
+        twap_window: msg.twap_window,
+        pricing_mode: msg.pricing_mode.clone().unwrap_or(PricingMode::Parimutuel),
+        tax_config,
     };
 
+    // Markets whose start_time is already reached open immediately
+    market_state.refresh_status(&market_config, env.block.time);
+
+    // A market that opens immediately is struck at the instantiate price(s)
+    if let (MarketStatus::Active, true, Some(initial_price)) = (
+        &market_state.status,
+        market_config.market_type.has_strike(),
+        &initial_price,
+    ) {
+        ensure_valid_strike(&market_config, initial_price)?;
+        if let Some(second_initial_price) = &second_initial_price {
+            ensure_valid_strike(&market_config, second_initial_price)?;
+        }
+        STRIKE_PRICE.save(
+            deps.storage,
+            &PriceSnapshot {
+                price: market_config.initial_price,
+                timestamp: initial_price.timestamp,
+            },
+        )?;
+        if let (Some(price), Some(second_initial_price)) =
+            (market_config.second_initial_price, &second_initial_price)
+        {
+            SECOND_STRIKE_PRICE.save(
+                deps.storage,
+                &PriceSnapshot {
+                    price,
+                    timestamp: second_initial_price.timestamp,
+                },
+            )?;
+        }
+    }
+
     MARKET_STATE.save(deps.storage, &market_state)?;
     CONFIG.save(deps.storage, &market_config)?;
 
@@ -180,17 +376,26 @@ pub fn instantiate(
                 .add_attribute("resolution_source", msg.resolution_source)
                 .add_attribute("oracle", msg.oracle)
                 .add_attribute("asset_to_track", msg.asset_to_track)
+                .add_attribute(
+                    "second_asset_to_track",
+                    market_config.second_asset_to_track.clone().unwrap_or_default(),
+                )
                 .add_attribute("market_type", msg.market_type.to_string())
                 .add_attribute("target_price", msg.target_price.to_string())
                 .add_attribute("initial_price", market_config.initial_price.to_string())
+                .add_attribute(
+                    "resolution_deadline",
+                    market_config.resolution_deadline.to_string(),
+                )
+                .add_attribute("resolution_mode", msg.resolution_mode.to_string())
+                .add_attribute("resolution_bounty", msg.resolution_bounty.to_string())
                 .add_attribute(
                     "initial_odds",
-                    cosmwasm_std::to_json_string(&msg.market_type.create_option_odds(Decimal::zero(), Decimal::zero()))
+                    cosmwasm_std::to_json_string(&market_state.create_type_safe_odds(deps.storage, &market_config)?)
                     .unwrap_or_else(|_| "[]".to_string()),
                 )
         )
-        .add_message(CosmosMsg::Any(issue_token_a.to_any()))
-        .add_message(CosmosMsg::Any(issue_token_b.to_any())))
+        .add_messages(issue_msgs))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -201,23 +406,100 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::BuyShare { market_id, option } => buy_share(deps, env, info, market_id, option),
+        ExecuteMsg::BuyShare {
+            market_id,
+            option,
+            min_amount_out,
+            min_odds,
+        } => buy_share(deps, env, info, market_id, option, min_amount_out, min_odds),
         ExecuteMsg::Resolve {
             market_id,
         } => resolve(deps, env, info, market_id),
         ExecuteMsg::Withdraw { market_id } => withdraw(deps, env, info, market_id),
-        ExecuteMsg::SellShare { option } => sell_share(deps, env, info, option),
+        ExecuteMsg::SellShare {
+            option,
+            min_amount_out,
+            max_tax_rate,
+        } => sell_share(deps, env, info, option, min_amount_out, max_tax_rate),
+        ExecuteMsg::CancelMarket { market_id } => cancel_market(deps, env, info, market_id),
+        ExecuteMsg::ClaimRefund { market_id } => claim_refund(deps, env, info, market_id),
+        ExecuteMsg::VoidMarket { market_id } => void_market(deps, env, info, market_id),
+        ExecuteMsg::SnapshotPrice { market_id } => snapshot_price(deps, env, info, market_id),
+        ExecuteMsg::DisputeResolution { market_id } => {
+            dispute_resolution(deps, env, info, market_id)
+        }
+        ExecuteMsg::ArbitrateDispute {
+            market_id,
+            winning_option,
+        } => arbitrate_dispute(deps, env, info, market_id, winning_option),
+        ExecuteMsg::FinalizeResolution { market_id } => {
+            finalize_resolution(deps, env, info, market_id)
+        }
+        ExecuteMsg::StartMarket { market_id } => start_market(deps, env, info, market_id),
+        ExecuteMsg::ObservePrice { market_id } => observe_price(deps, env, info, market_id),
+        ExecuteMsg::SubmitOutcome {
+            market_id,
+            winning_option,
+        } => submit_outcome(deps, env, info, market_id, winning_option),
+        ExecuteMsg::MintCompleteSet { market_id } => {
+            mint_complete_set(deps, env, info, market_id)
+        }
+        ExecuteMsg::RedeemCompleteSet { market_id } => {
+            redeem_complete_set(deps, env, info, market_id)
+        }
+        ExecuteMsg::FundLiquidity { market_id } => fund_liquidity(deps, env, info, market_id),
+        ExecuteMsg::WithdrawLiquidity { market_id } => {
+            withdraw_liquidity(deps, env, info, market_id)
+        }
+        ExecuteMsg::SetHouseLine {
+            market_id,
+            option,
+            odds,
+            liability_cap,
+        } => set_house_line(deps, env, info, market_id, option, odds, liability_cap),
+        ExecuteMsg::ProvideLiquidity { market_id, option } => {
+            provide_liquidity(deps, env, info, market_id, option)
+        }
+        ExecuteMsg::RemoveLiquidity {
+            market_id,
+            option,
+            lp_shares,
+        } => remove_liquidity(deps, env, info, market_id, option, lp_shares),
+        ExecuteMsg::Swap {
+            market_id,
+            option,
+            min_amount_out,
+        } => swap(deps, env, info, market_id, option, min_amount_out),
+        ExecuteMsg::PlaceOrder {
+            market_id,
+            option,
+            side,
+            price,
+            amount,
+        } => place_order(deps, env, info, market_id, option, side, price, amount),
+        ExecuteMsg::CancelOrder {
+            market_id,
+            order_id,
+        } => cancel_order(deps, env, info, market_id, order_id),
+        ExecuteMsg::SetManualPrice {
+            market_id,
+            asset,
+            price,
+        } => set_manual_price(deps, env, info, market_id, asset, price),
     }
 }
 
 pub fn sell_share(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     option: String,
+    min_amount_out: Option<Uint128>,
+    max_tax_rate: Option<Decimal>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
 
     // Check if market is still active (can't sell after resolved)
     if matches!(market_state.status, MarketStatus::Resolved(_)) {
@@ -225,6 +507,9 @@ pub fn sell_share(
             "Cannot sell shares after market is resolved",
         )));
     }
+    ensure_market_active(&market_state, &config)?;
+    ensure_barrier_not_hit(deps.as_ref())?;
+    record_strike_price(&mut deps, &config, env.block.time)?;
 
     // Find the matching market option
     let market_option = config
@@ -239,16 +524,61 @@ pub fn sell_share(
     // Amount the user wants to sell
     let amount_sent = must_pay(&info, &associated_token_denom)?;
 
+    // Value of the tokens sold: 1:1, or along the cost function with LMSR
+    let sell_value = match &config.pricing_mode {
+        PricingMode::Parimutuel => amount_sent,
+        PricingMode::Lmsr { liquidity } => {
+            let index = config
+                .pairs
+                .iter()
+                .position(|p| p.text == market_option.text)
+                .unwrap_or_default();
+            let value =
+                market_state.lmsr_sell_value(deps.storage, &config, *liquidity, index, amount_sent)?;
+            LMSR_QUANTITIES.update(
+                deps.storage,
+                &market_option.text,
+                |quantity| -> StdResult<Uint128> {
+                    Ok(quantity.unwrap_or_default().saturating_sub(amount_sent))
+                },
+            )?;
+            value
+        }
+        PricingMode::FixedOdds => return Err(ContractError::FixedOddsSell {}),
+    };
+
     // Calculate time-based tax
     let tax_rate = market_state.calculate_time_based_tax(&config, env.block.time);
     let amount_after_tax =
-        market_state.calculate_sell_amount_with_tax(&config, amount_sent, env.block.time);
-    let tax_amount = amount_sent - amount_after_tax;
+        market_state.calculate_sell_amount_with_tax(&config, sell_value, env.block.time);
+    let tax_amount = sell_value - amount_after_tax;
 
     // Calculate commission on the amount after tax using BPS (basis points)
     let commission_amount = amount_after_tax * config.commission_rate / Uint128::from(10000u128);
     let final_amount = amount_after_tax - commission_amount;
 
+    // Slippage guards, a late transaction pays more tax
+    if let Some(max_tax_rate) = max_tax_rate {
+        if tax_rate > max_tax_rate {
+            return Err(ContractError::TaxRateExceeded {
+                max_tax_rate,
+                tax_rate,
+            });
+        }
+    }
+    if let Some(min_amount_out) = min_amount_out {
+        if final_amount < min_amount_out {
+            return Err(ContractError::SlippageExceeded {
+                min_amount_out,
+                amount_out: final_amount,
+            });
+        }
+    }
+
+    // Part of the commission may be kept to fund the resolution bounty
+    let bounty_amount = market_state.reserve_bounty(&config, commission_amount);
+    let admin_commission = commission_amount - bounty_amount;
+
     // Update share using Map - O(1) operation
     SHARES.update(
         deps.storage,
@@ -266,18 +596,38 @@ pub fn sell_share(
             }
         },
     )?;
+    update_share_total(
+        deps.storage,
+        &market_option.text,
+        Uint128::zero(),
+        amount_sent,
+    )?;
 
     // Update aggregate totals - reduce by final amount (user's effective stake decrease)
-    if market_option.text == config.pairs[0].text {
-        market_state.total_stake_option_a -= final_amount;
-    } else {
-        market_state.total_stake_option_b -= final_amount;
-    }
+    OPTION_TOTALS.update(
+        deps.storage,
+        &market_option.text,
+        |total| -> StdResult<Uint128> {
+            let total = total.unwrap_or_default();
+            match config.pricing_mode {
+                PricingMode::Parimutuel => Ok(total.checked_sub(final_amount)?),
+                // An LMSR sale can return more than was staked on the option
+                PricingMode::Lmsr { .. } | PricingMode::FixedOdds => {
+                    Ok(total.saturating_sub(final_amount))
+                }
+            }
+        },
+    )?;
 
     // Update total value - only reduce by the amount returned to user
-    // The tax and commission amounts stay in the pot, benefiting remaining participants
+    // The tax and commission amounts stay in the pot, benefiting remaining participants.
+    // LMSR funds pay the commission too, the tax stays with the LMSR
+    let paid_out = match config.pricing_mode {
+        PricingMode::Parimutuel => final_amount,
+        PricingMode::Lmsr { .. } | PricingMode::FixedOdds => amount_after_tax,
+    };
     let new_total_value =
-        Uint128::from_str(&market_state.total_value.amount).unwrap() - final_amount;
+        Uint128::from_str(&market_state.total_value.amount).unwrap() - paid_out;
 
     market_state.total_value.amount = new_total_value.to_string();
 
@@ -310,17 +660,20 @@ pub fn sell_share(
             }],
         };
 
+        messages.push(CosmosMsg::Any(return_msg.to_any()));
+    }
+
+    // The bounty reserve may have taken the whole commission
+    if !admin_commission.is_zero() {
         //send comission to admin
         let commission_to_admin_msg = MsgSend {
             from_address: env.contract.address.to_string(),
             to_address: config.admin.to_string(),
             amount: vec![Coin {
                 denom: config.buy_token.clone(),
-                amount: commission_amount.to_string(),
+                amount: admin_commission.to_string(),
             }],
         };
-        
-        messages.push(CosmosMsg::Any(return_msg.to_any()));
         messages.push(CosmosMsg::Any(commission_to_admin_msg.to_any()))
     }
 
@@ -334,17 +687,19 @@ pub fn sell_share(
                 .add_attribute("market_id", config.clone().id)
                 .add_attribute("option", market_option.text)
                 .add_attribute("tokens_sent", amount_sent.to_string())
+                .add_attribute("sell_value", sell_value.to_string())
                 .add_attribute("amount_after_tax", amount_after_tax.to_string())
                 .add_attribute("tax_amount", tax_amount.to_string())
                 .add_attribute("tax_rate", tax_rate.to_string())
                 .add_attribute("commission_amount", commission_amount.to_string())
+                .add_attribute("bounty_amount", bounty_amount.to_string())
                 .add_attribute("final_amount", final_amount.to_string())
                 .add_attribute("user", info.sender)
                 .add_attribute("total_value", new_total_value.to_string())
                 .add_attribute("total_volume", market_state.volume.to_string())
                 .add_attribute(
                     "odds",
-                    cosmwasm_std::to_json_string(&market_state.create_type_safe_odds(&config))
+                    cosmwasm_std::to_json_string(&market_state.create_type_safe_odds(deps.storage, &config)?)
                     .unwrap_or_else(|_| "[]".to_string()),
                 ),
         )
@@ -352,14 +707,17 @@ pub fn sell_share(
 }
 
 pub fn buy_share(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     _market_id: String,
     option: String,
+    min_amount_out: Option<Uint128>,
+    min_odds: Option<Decimal>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
 
     let payment: Uint128 = must_pay(&info, &config.buy_token)?;
 
@@ -373,6 +731,9 @@ pub fn buy_share(
             "Market is already resolved",
         )));
     }
+    ensure_market_active(&market_state, &config)?;
+    ensure_barrier_not_hit(deps.as_ref())?;
+    record_strike_price(&mut deps, &config, env.block.time)?;
 
     // Find the matching market option
     let market_option = config
@@ -382,18 +743,50 @@ pub fn buy_share(
         .cloned()
         .ok_or_else(|| StdError::generic_err("Invalid option"))?;
 
-    // Check if this is a new bettor (no existing shares for either option)
-    let has_any_shares = SHARES
-        .may_load(deps.storage, (&info.sender, &config.pairs[0].text))?
-        .is_some()
-        || SHARES
-            .may_load(deps.storage, (&info.sender, &config.pairs[1].text))?
-            .is_some();
+    // Check if this is a new bettor (no existing shares for any option)
+    let mut has_any_shares = false;
+    for pair in config.pairs.iter() {
+        if SHARES.has(deps.storage, (&info.sender, &pair.text)) {
+            has_any_shares = true;
+            break;
+        }
+    }
 
     if !has_any_shares {
         market_state.num_bettors += 1;
     }
 
+    // Tokens bought: 1:1 with the net payment, along the cost function with LMSR,
+    // or the locked payout with fixed odds
+    let tokens = match &config.pricing_mode {
+        PricingMode::Parimutuel => net_payment,
+        PricingMode::Lmsr { liquidity } => {
+            ensure_lmsr_funded(deps.storage, &config)?;
+            let index = config
+                .pairs
+                .iter()
+                .position(|p| p.text == market_option.text)
+                .unwrap_or_default();
+            let tokens =
+                market_state.lmsr_buy_quantity(deps.storage, &config, *liquidity, index, net_payment)?;
+            LMSR_QUANTITIES.update(
+                deps.storage,
+                &market_option.text,
+                |quantity| -> StdResult<Uint128> { Ok(quantity.unwrap_or_default() + tokens) },
+            )?;
+            tokens
+        }
+        PricingMode::FixedOdds => place_fixed_odds_bet(
+            deps.storage,
+            &config,
+            &market_state,
+            &info.sender,
+            &market_option.text,
+            net_payment,
+            env.block.time,
+        )?,
+    };
+
     // Update or create share using Map - O(1) operation
     SHARES.update(
         deps.storage,
@@ -401,27 +794,53 @@ pub fn buy_share(
         |existing| -> StdResult<Share> {
             match existing {
                 Some(mut share) => {
-                    share.amount += net_payment;
+                    share.amount += tokens;
                     Ok(share)
                 }
                 None => Ok(Share {
-                    amount: net_payment,
+                    amount: tokens,
                     has_withdrawn: false,
                 }),
             }
         },
     )?;
+    update_share_total(deps.storage, &market_option.text, tokens, Uint128::zero())?;
 
     // Update aggregate totals
-    if market_option.text == config.pairs[0].text {
-        market_state.total_stake_option_a += net_payment;
-    } else {
-        market_state.total_stake_option_b += net_payment;
+    OPTION_TOTALS.update(
+        deps.storage,
+        &market_option.text,
+        |total| -> StdResult<Uint128> { Ok(total.unwrap_or_default() + net_payment) },
+    )?;
+
+    // Slippage guards, earlier bets in the same block may have moved the odds
+    if let Some(min_amount_out) = min_amount_out {
+        if tokens < min_amount_out {
+            return Err(ContractError::SlippageExceeded {
+                min_amount_out,
+                amount_out: tokens,
+            });
+        }
+    }
+    if let Some(min_odds) = min_odds {
+        let index = config
+            .pairs
+            .iter()
+            .position(|p| p.text == market_option.text)
+            .unwrap_or_default();
+        let odds = market_state.calculate_odds(deps.storage, &config)?[index];
+        if odds < min_odds {
+            return Err(ContractError::OddsBelowMinimum { min_odds, odds });
+        }
     }
 
     // Update volume
     market_state.volume += payment;
 
+    // Part of the commission may be kept to fund the resolution bounty
+    let bounty_amount = market_state.reserve_bounty(&config, commission_amount);
+    let admin_commission = commission_amount - bounty_amount;
+
     // Update total value
     let new_total_value = Uint128::from_str(&market_state.total_value.amount).unwrap() + net_payment;
     market_state.total_value.amount = new_total_value.to_string();
@@ -434,26 +853,29 @@ pub fn buy_share(
         sender: env.contract.address.to_string(),
         coin: Some(Coin {
             denom: market_option.associated_token_denom.clone(),
-            amount: net_payment.to_string(),
+            amount: tokens.to_string(),
         }),
         recipient: info.sender.to_string(),
     };
 
-       //send comission to admin
-    let commission_to_admin_msg = MsgSend {
-        from_address: env.contract.address.to_string(),
-        to_address: config.admin.to_string(),
-        amount: vec![Coin {
-            denom: config.buy_token.clone(),
-            amount: commission_amount.to_string(),
-        }],
-    };
-
+    let mut response = Response::new();
 
-    let response = Response::new();
+    // The bounty reserve may have taken the whole commission
+    if !admin_commission.is_zero() {
+        //send comission to admin
+        let commission_to_admin_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: config.admin.to_string(),
+            amount: vec![Coin {
+                denom: config.buy_token.clone(),
+                amount: admin_commission.to_string(),
+            }],
+        };
+        response = response.add_message(CosmosMsg::Any(commission_to_admin_msg.to_any()));
+    }
 
     // Use type-safe odds creation
-    let odds = market_state.create_type_safe_odds(&config);
+    let odds = market_state.create_type_safe_odds(deps.storage, &config)?;
 
     let total_value = Uint128::from_str(&market_state.total_value.amount).unwrap();
 
@@ -464,7 +886,9 @@ pub fn buy_share(
                 .add_attribute("option", market_option.text)
                 .add_attribute("amount", payment.to_string())
                 .add_attribute("net_amount", net_payment.to_string())
+                .add_attribute("tokens", tokens.to_string())
                 .add_attribute("commission_amount", commission_amount.to_string())
+                .add_attribute("bounty_amount", bounty_amount.to_string())
                 .add_attribute("user", info.sender.to_string())
                 .add_attribute("total_value", total_value.to_string())
                 .add_attribute("total_volume", market_state.volume.to_string())
@@ -473,58 +897,167 @@ pub fn buy_share(
                     cosmwasm_std::to_json_string(&odds).unwrap_or_else(|_| "[]".to_string()),
                 ),
         )
-        .add_message(CosmosMsg::Any(commission_to_admin_msg.to_any()))
         .add_message(CosmosMsg::Any(mint_msg.to_any())))
 }
 
+/// Rejects markets that already have, or are about to have, a final outcome
+fn ensure_not_settled(market_state: &MarketState) -> Result<(), ContractError> {
+    match market_state.status {
+        MarketStatus::Resolved(_)
+        | MarketStatus::ProposedResolution { .. }
+        | MarketStatus::Disputed(_) => Err(ContractError::Std(StdError::generic_err(
+            "Market is already resolved",
+        ))),
+        MarketStatus::Cancelled => Err(ContractError::MarketCancelled {}),
+        MarketStatus::Void => Err(ContractError::MarketVoid {}),
+        _ => Ok(()),
+    }
+}
+
+/// Most the LMSR can lose, b * ln(number of options) rounded up, funded before trading
+fn lmsr_required_subsidy(config: &Config, liquidity: Uint128) -> StdResult<Uint128> {
+    let max_loss = lmsr::max_loss(Decimal256::from_ratio(liquidity, 1u128), config.pairs.len())?;
+    Ok(Uint128::try_from(max_loss.to_uint_ceil())?)
+}
+
+fn ensure_lmsr_funded(storage: &dyn Storage, config: &Config) -> Result<(), ContractError> {
+    let PricingMode::Lmsr { liquidity } = config.pricing_mode else {
+        return Ok(());
+    };
+    if LMSR_SUBSIDY.may_load(storage)?.unwrap_or_default().is_zero() {
+        return Err(ContractError::LmsrNotFunded {
+            required: lmsr_required_subsidy(config, liquidity)?,
+        });
+    }
+    Ok(())
+}
+
+/// Records a bet at the current house odds and returns its locked payout.
+/// The payout must fit under the option's liability cap and the house funds must cover
+/// the worst outcome, the stake included
+fn place_fixed_odds_bet(
+    storage: &mut dyn Storage,
+    config: &Config,
+    market_state: &MarketState,
+    user: &Addr,
+    option: &str,
+    stake: Uint128,
+    placed_at: Timestamp,
+) -> Result<Uint128, ContractError> {
+    let line = HOUSE_LINES
+        .may_load(storage, option)?
+        .ok_or_else(|| ContractError::NoHouseLine {
+            option: option.to_string(),
+        })?;
+    let payout = stake + stake.mul_floor(line.odds);
+
+    let liability = LIABILITIES.may_load(storage, option)?.unwrap_or_default() + payout;
+    if liability > line.liability_cap {
+        return Err(ContractError::LiabilityCapExceeded {
+            option: option.to_string(),
+            liability_cap: line.liability_cap,
+            liability,
+        });
+    }
+    LIABILITIES.save(storage, option, &liability)?;
+
+    let mut required = Uint128::zero();
+    for pair in &config.pairs {
+        required = required.max(LIABILITIES.may_load(storage, &pair.text)?.unwrap_or_default());
+    }
+    let available = Uint128::from_str(&market_state.total_value.amount)? + stake;
+    if required > available {
+        return Err(ContractError::InsufficientBankroll {
+            required,
+            available,
+        });
+    }
+
+    let id = NEXT_BET_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_BET_ID.save(storage, &(id + 1))?;
+    BETS.save(
+        storage,
+        (user, id),
+        &FixedOddsBet {
+            id,
+            option: option.to_string(),
+            stake,
+            odds: line.odds,
+            payout,
+            placed_at,
+        },
+    )?;
+
+    Ok(payout)
+}
+
+/// Buys and sells are only allowed while the market is Active
+fn ensure_market_active(market_state: &MarketState, config: &Config) -> Result<(), ContractError> {
+    match market_state.status {
+        MarketStatus::Active => Ok(()),
+        MarketStatus::Pending => Err(ContractError::MarketNotStarted {
+            start_time: config.start_time,
+        }),
+        MarketStatus::Cancelled => Err(ContractError::MarketCancelled {}),
+        MarketStatus::Void => Err(ContractError::MarketVoid {}),
+        _ => Err(ContractError::MarketClosed {
+            end_time: config.end_time,
+        }),
+    }
+}
+
 pub fn resolve(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     _market_id: String,
 ) -> Result<Response, ContractError> {
-
-    //TODO: check how can we get the price at resolve time!
-    //Right now we get the latest price wihcih might be different then the price at end_time!
-    //Etheir a relayer call this function at the right time or maybe clp_feed can keep history
     let config = CONFIG.load(deps.storage)?;
     let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    // A resolver committee replaces the admin, its members vote through SubmitOutcome
+    if config.resolvers.is_some() {
+        return Err(ContractError::ResolvedByCommittee {});
+    }
 
     // Ensure only the admin can resolve the market --> The relayer
-    if info.sender != config.admin {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Unauthorized: Only the admin can resolve markets",
-        )));
+    // Permissionless markets can be resolved by anyone (keepers) for the bounty
+    if config.resolution_mode == ResolutionMode::AdminOnly && info.sender != config.admin {
+        return Err(ContractError::UnauthorizedResolver {});
     }
 
-    // Check if the market has ended
-    if env.block.time < config.end_time {
-        return Err(ContractError::Std(StdError::generic_err(
-            "Market has not ended yet",
-        )));
+    if !config.market_type.is_price_based() {
+        return Err(ContractError::NotPriceMarket {});
     }
 
-    // Check if the market is already resolved
-    if matches!(market_state.status, MarketStatus::Resolved(_)) {
+    // Touch markets can be resolved as soon as the barrier is hit
+    let barrier_hit = BARRIER_HIT.may_load(deps.storage)?;
+
+    // Check if the market has ended
+    if env.block.time < config.end_time && barrier_hit.is_none() {
         return Err(ContractError::Std(StdError::generic_err(
-            "Market is already resolved",
+            "Market has not ended yet",
         )));
     }
 
-    // Get current price from oracle
-    let oracle = ClpFeedQuerier::new(&deps.querier, config.oracle.clone());
-    let current_price_response = oracle.query_price(config.asset_to_track.clone())?;
+    // Check if the market is already resolved, cancelled or void
+    ensure_not_settled(&market_state)?;
 
-    let current_price = match current_price_response.price {
-        Some(price_info) => Decimal::from_str(&price_info.price)
-            .map_err(|_| StdError::generic_err("Invalid price format from oracle"))?,
-        None => return Err(ContractError::Std(StdError::generic_err(
-            "No price available from oracle"
-        ))),
-    };
+    // Past the deadline the market can only be voided
+    if env.block.time >= config.resolution_deadline {
+        return Err(ContractError::ResolutionDeadlinePassed {
+            deadline: config.resolution_deadline,
+        });
+    }
 
-    // Determine winning option based on market type and price comparison
-    let winning_option_text = config.market_type.determine_winner(current_price, config.target_price);
+    let PriceOutcome {
+        settlement,
+        second_settlement,
+        target_price,
+        winner: winning_option_text,
+    } = settle_price_outcome(&mut deps, &config)?;
+    let current_price = settlement.price;
 
     let winning_option_obj = config
         .pairs
@@ -536,20 +1069,33 @@ pub fn resolve(
         ))?;
 
     // Calculate type-safe final odds before updating the market state
-    let final_odds = market_state.create_type_safe_odds(&config);
-
-    // Update the market status with the winning option
-    market_state.status = MarketStatus::Resolved(winning_option_obj);
-
-    // Save the updated market state
-    MARKET_STATE.save(deps.storage, &market_state)?;
+    let final_odds = market_state.create_type_safe_odds(deps.storage, &config)?;
+
+    // Keep the settlement evidence in state so it can be audited without an indexer
+    let resolution = ResolutionRecord {
+        winning_option: winning_option_obj.clone(),
+        price: current_price,
+        price_timestamp: settlement.timestamp,
+        oracle: config.oracle.clone(),
+        resolver: info.sender.clone(),
+        block_height: env.block.height,
+        resolved_at: env.block.time,
+        initial_price: config.initial_price,
+        target_price,
+        scalar_payout: config.market_type.scalar_long_payout(current_price),
+        second_price: second_settlement.as_ref().map(|settlement| settlement.price),
+    };
+    let (response, bounty) =
+        apply_resolution(deps.storage, &env, &config, &mut market_state, resolution)?;
 
-    Ok(Response::new().add_event(
+    Ok(response.add_event(
         Event::new("cc_prediction_market_resolve")
             .add_attribute("market_id", config.id)
             .add_attribute("winning_option", winning_option_text)
             .add_attribute("current_price", current_price.to_string())
-            .add_attribute("target_price", config.target_price.to_string())
+            .add_attribute("price_timestamp", settlement.timestamp.to_string())
+            .add_attribute("bounty", bounty.to_string())
+            .add_attribute("target_price", target_price.to_string())
             .add_attribute("initial_price", config.initial_price.to_string())
             .add_attribute("user", info.sender.to_string())
             .add_attribute("total_value", market_state.total_value.amount.to_string())
@@ -560,83 +1106,2567 @@ pub fn resolve(
     ))
 }
 
-pub fn withdraw(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    market_id: String,
-) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    let market_state = MARKET_STATE.load(deps.storage)?;
+/// Settlement evidence of a price market and the option it makes win
+struct PriceOutcome {
+    settlement: PriceSnapshot,
+    second_settlement: Option<PriceSnapshot>,
+    target_price: Decimal,
+    winner: String,
+}
 
-    // Get winning option (also checks if market is resolved)
-    let winning_option = match &market_state.status {
-        MarketStatus::Resolved(option) => option,
-        _ => {
-            return Err(ContractError::Std(StdError::generic_err(
-                "Market is not resolved yet",
-            )))
+/// Settles the price of a price market and determines the option it makes win
+fn settle_price_outcome(
+    deps: &mut DepsMut,
+    config: &Config,
+) -> Result<PriceOutcome, ContractError> {
+    let barrier_hit = BARRIER_HIT.may_load(deps.storage)?;
+
+    let settlement = match &barrier_hit {
+        Some(hit) => hit.clone(),
+        None => settle_price(deps, config, &config.asset_to_track, &SETTLEMENT_PRICE)?,
+    };
+
+    let second_settlement = match &config.second_asset_to_track {
+        Some(second_asset) => Some(settle_price(
+            deps,
+            config,
+            second_asset,
+            &SECOND_SETTLEMENT_PRICE,
+        )?),
+        None => None,
+    };
+
+    let current_price = settlement.price;
+
+    // UpDown markets compare against the price at start_time, the others against target_price
+    let target_price = match config.market_type {
+        MarketType::UpDown | MarketType::Relative => {
+            STRIKE_PRICE
+                .may_load(deps.storage)?
+                .ok_or(ContractError::StrikePriceNotSet {})?
+                .price
+        }
+        _ => config.target_price,
+    };
+
+    // Determine winning option based on market type and price comparison
+    let winning_option_text = match config.market_type {
+        // The settlement price is the last point of the observed path
+        MarketType::Touch => {
+            let winner = if barrier_hit.is_some() || barrier_touched(config, current_price) {
+                config.market_type.get_winning_option_when_target_reached()
+            } else {
+                config.market_type.get_winning_option_when_target_not_reached()
+            };
+            winner.unwrap_or_default().to_string()
+        }
+        // Compare the performance of both assets since their strikes
+        MarketType::Relative => {
+            let second_strike = SECOND_STRIKE_PRICE
+                .may_load(deps.storage)?
+                .ok_or(ContractError::StrikePriceNotSet {})?;
+            let second_price = second_settlement
+                .as_ref()
+                .ok_or(ContractError::InvalidSecondAsset {})?
+                .price;
+
+            let performance = current_price / target_price;
+            let second_performance = second_price / second_strike.price;
+            config.market_type.determine_winner(performance, second_performance)
         }
+        _ => config.market_type.determine_winner(current_price, target_price),
     };
 
-    // Check that the user sent the winning token denom to withdraw their winnings
-    let _amount: Uint128 = must_pay(&info, &winning_option.associated_token_denom)?;
+    Ok(PriceOutcome {
+        settlement,
+        second_settlement,
+        target_price,
+        winner: winning_option_text,
+    })
+}
 
-    // Load and check user's winning share using Map - O(1) operation
-    let share = SHARES
-        .may_load(deps.storage, (&info.sender, &winning_option.text))?
-        .ok_or_else(|| StdError::generic_err("No winning shares found for user"))?;
+pub fn submit_outcome(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    winning_option: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    let resolvers = config
+        .resolvers
+        .clone()
+        .ok_or(ContractError::UnauthorizedOutcomeSubmitter {})?;
+    if !resolvers.members.contains(&info.sender) {
+        return Err(ContractError::UnauthorizedOutcomeSubmitter {});
+    }
 
-    if share.has_withdrawn {
+    // The outcome is decided by end_time, Touch markets as soon as the barrier is hit
+    if env.block.time < config.end_time && BARRIER_HIT.may_load(deps.storage)?.is_none() {
         return Err(ContractError::Std(StdError::generic_err(
-            "User has already withdrawn their winnings",
+            "Market has not ended yet",
         )));
     }
 
-    // Mark share as withdrawn
-    SHARES.update(
-        deps.storage,
-        (&info.sender, &winning_option.text),
-        |existing| -> StdResult<Share> {
-            match existing {
-                Some(mut s) => {
-                    s.has_withdrawn = true;
-                    Ok(s)
-                }
-                None => Err(StdError::generic_err("No shares found")),
-            }
-        },
-    )?;
+    ensure_not_settled(&market_state)?;
 
-    let total_winnings = market_state.calculate_winnings(deps.storage, &info.sender, &config)?;
+    if env.block.time >= config.resolution_deadline {
+        return Err(ContractError::ResolutionDeadlinePassed {
+            deadline: config.resolution_deadline,
+        });
+    }
 
-    // Create bank transfer message
-    let transfer_msg = MsgSend {
-        from_address: env.contract.address.to_string(),
-        to_address: info.sender.to_string(),
-        amount: vec![Coin {
-            amount: total_winnings.amount.clone(),
-            denom: total_winnings.denom,
-        }],
-    };
+    let winning_option_obj = config
+        .pairs
+        .iter()
+        .find(|p| p.text == winning_option)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("Invalid option"))?;
 
-    let response = Response::new();
+    // Members vote on the option, the settled price is kept as evidence for the record
+    // and for the arbitrator
+    let price_outcome = if config.market_type.is_price_based() {
+        Some(settle_price_outcome(&mut deps, &config)?)
+    } else {
+        None
+    };
 
-    Ok(response
-        .add_event(
-            Event::new("cc_prediction_market_withdraw")
-                .add_attribute("market_id", market_id)
-                .add_attribute("user", info.sender.to_string())
-                .add_attribute("winning_option", winning_option.text.clone())
-                .add_attribute("total_winnings", total_winnings.amount.to_string()),
-        )
-        .add_message(CosmosMsg::Any(transfer_msg.to_any())))
-}
+    let (event, agreed) = record_outcome_vote(
+        deps.storage,
+        &config,
+        &mut market_state,
+        &resolvers,
+        &info.sender,
+        &winning_option_obj,
+    )?;
+    if !agreed {
+        MARKET_STATE.save(deps.storage, &market_state)?;
+        return Ok(Response::new().add_event(event));
+    }
+
+    let final_odds = market_state.create_type_safe_odds(deps.storage, &config)?;
+
+    let mut resolution = ResolutionRecord {
+        winning_option: winning_option_obj.clone(),
+        price: Decimal::zero(),
+        price_timestamp: env.block.time,
+        oracle: config.oracle.clone(),
+        resolver: info.sender.clone(),
+        block_height: env.block.height,
+        resolved_at: env.block.time,
+        initial_price: config.initial_price,
+        target_price: config.target_price,
+        scalar_payout: None,
+        second_price: None,
+    };
+    if let Some(outcome) = price_outcome {
+        resolution.price = outcome.settlement.price;
+        resolution.price_timestamp = outcome.settlement.timestamp;
+        resolution.target_price = outcome.target_price;
+        resolution.second_price = outcome.second_settlement.map(|settlement| settlement.price);
+        resolution.scalar_payout = agreed_scalar_payout(
+            &config,
+            &winning_option_obj,
+            &outcome.winner,
+            outcome.settlement.price,
+        );
+    }
+    let (response, bounty) =
+        apply_resolution(deps.storage, &env, &config, &mut market_state, resolution)?;
+
+    Ok(response.add_event(event).add_event(
+        Event::new("cc_prediction_market_resolve")
+            .add_attribute("market_id", config.id)
+            .add_attribute("winning_option", winning_option)
+            .add_attribute("resolution_source", config.resolution_source)
+            .add_attribute("bounty", bounty.to_string())
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("total_value", market_state.total_value.amount.to_string())
+            .add_attribute(
+                "final_odds",
+                cosmwasm_std::to_json_string(&final_odds).unwrap_or_else(|_| "[]".to_string()),
+            ),
+    ))
+}
+
+/// Long payout of a scalar market resolved to `option`, None for other market types.
+/// An option matching the price pays out by the price, one against it pays its side the whole pot
+fn agreed_scalar_payout(
+    config: &Config,
+    option: &MarketOption,
+    price_winner: &str,
+    price: Decimal,
+) -> Option<Decimal> {
+    let payout = config.market_type.scalar_long_payout(price)?;
+    Some(if option.text == price_winner {
+        payout
+    } else if *option == config.pairs[0] {
+        Decimal::one()
+    } else {
+        Decimal::zero()
+    })
+}
+
+/// Records a resolver's vote for `option` and tallies all votes.
+/// Returns the vote event, and whether the threshold of resolvers now agree on `option`.
+/// Once no option can reach the threshold the market is disputed instead,
+/// the arbitrator then rules on it
+fn record_outcome_vote(
+    storage: &mut dyn Storage,
+    config: &Config,
+    market_state: &mut MarketState,
+    resolvers: &ResolverSet,
+    voter: &Addr,
+    option: &MarketOption,
+) -> Result<(Event, bool), ContractError> {
+    // A resolver may change their vote while the outcome is open
+    OUTCOME_VOTES.save(storage, voter, &option.text)?;
+
+    // Votes per option, in the order of config.pairs
+    let mut tally: Vec<u32> = vec![0; config.pairs.len()];
+    for vote in OUTCOME_VOTES.range(storage, None, None, cosmwasm_std::Order::Ascending) {
+        let (_, voted) = vote?;
+        if let Some(index) = config.pairs.iter().position(|p| p.text == voted) {
+            tally[index] += 1;
+        }
+    }
+    let votes = config
+        .pairs
+        .iter()
+        .position(|p| p.text == option.text)
+        .map(|index| tally[index])
+        .unwrap_or_default();
+    // Members who have not voted yet could still push an option over the threshold
+    let cast: u32 = tally.iter().sum();
+    let remaining = (resolvers.members.len() as u32).saturating_sub(cast);
+    let agreed = votes >= resolvers.threshold;
+    let conflict = !agreed
+        && tally
+            .iter()
+            .all(|count| count + remaining < resolvers.threshold);
+
+    let event = Event::new("cc_prediction_market_submit_outcome")
+        .add_attribute("market_id", config.id.clone())
+        .add_attribute("option", option.text.clone())
+        .add_attribute("votes", votes.to_string())
+        .add_attribute("threshold", resolvers.threshold.to_string())
+        .add_attribute("conflict", conflict.to_string())
+        .add_attribute("user", voter.to_string());
+
+    // The round ends once the committee agrees or is deadlocked, its votes don't carry over
+    if agreed || conflict {
+        OUTCOME_VOTES.clear(storage);
+    }
+
+    if conflict {
+        // No option can reach the threshold anymore, so the option with the most votes
+        // is put to the arbitrator, the first one on a tie
+        let mut leading = 0;
+        for (index, count) in tally.iter().enumerate() {
+            if *count > tally[leading] {
+                leading = index;
+            }
+        }
+        market_state.status = MarketStatus::Disputed(config.pairs[leading].clone());
+        return Ok((event, false));
+    }
+
+    Ok((event, agreed))
+}
+
+/// Saves the resolution record and moves the market to the winning option.
+/// Returns the response paying the resolver bounty, and the bounty amount.
+/// A proposed resolution only pays the bounty once it is final
+fn apply_resolution(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    market_state: &mut MarketState,
+    resolution: ResolutionRecord,
+) -> Result<(Response, Uint128), ContractError> {
+    RESOLUTION.save(storage, &resolution)?;
+
+    // Update the market status with the winning option.
+    // With disputes enabled the outcome is only proposed until the challenge period ends.
+    let mut response = Response::new();
+    let mut bounty = Uint128::zero();
+    match &config.dispute_config {
+        Some(dispute_config) => {
+            market_state.status = MarketStatus::ProposedResolution {
+                option: resolution.winning_option,
+                challenge_ends: env.block.time.plus_seconds(dispute_config.challenge_period),
+            };
+        }
+        None => {
+            market_state.status = MarketStatus::Resolved(resolution.winning_option);
+            // Pay the resolver whatever bounty the commissions have funded
+            let (bounty_msg, amount) = take_bounty(env, config, market_state, &resolution.resolver);
+            response = response.add_messages(bounty_msg);
+            bounty = amount;
+        }
+    }
+
+    // Save the updated market state
+    MARKET_STATE.save(storage, market_state)?;
+
+    Ok((response, bounty))
+}
+
+/// Empties the bounty reserve, returns the message paying it to `recipient` and the amount
+fn take_bounty(
+    env: &Env,
+    config: &Config,
+    market_state: &mut MarketState,
+    recipient: &Addr,
+) -> (Option<CosmosMsg>, Uint128) {
+    let bounty = market_state.bounty_reserve;
+    market_state.bounty_reserve = Uint128::zero();
+    if bounty.is_zero() {
+        return (None, bounty);
+    }
+
+    let bounty_msg = MsgSend {
+        from_address: env.contract.address.to_string(),
+        to_address: recipient.to_string(),
+        amount: vec![Coin {
+            denom: config.buy_token.clone(),
+            amount: bounty.to_string(),
+        }],
+    };
+    (Some(CosmosMsg::Any(bounty_msg.to_any())), bounty)
+}
+
+/// Saves `market_state`, a proposed resolution stays stored until FinalizeResolution pays its bounty
+fn save_market_state(storage: &mut dyn Storage, market_state: &MarketState) -> StdResult<()> {
+    let stored = MARKET_STATE.load(storage)?;
+    if let MarketStatus::ProposedResolution { .. } = stored.status {
+        return MARKET_STATE.save(
+            storage,
+            &MarketState {
+                status: stored.status,
+                ..market_state.clone()
+            },
+        );
+    }
+    MARKET_STATE.save(storage, market_state)
+}
+
+/// Makes an unchallenged proposed resolution final and pays the resolver bounty
+pub fn finalize_resolution(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+
+    // Only a proposal still stored as such is finalized, and only once
+    let MarketStatus::ProposedResolution { challenge_ends, .. } = &market_state.status else {
+        return Err(ContractError::NoProposedResolution {});
+    };
+    if env.block.time < *challenge_ends {
+        return Err(ContractError::ChallengePeriodNotEnded {
+            challenge_ends: *challenge_ends,
+        });
+    }
+    market_state.refresh_status(&config, env.block.time);
+    let winning_option = match &market_state.status {
+        MarketStatus::Resolved(option) => option.clone(),
+        _ => return Err(ContractError::NoProposedResolution {}),
+    };
+
+    let resolution = RESOLUTION.load(deps.storage)?;
+    let (bounty_msg, bounty) = take_bounty(&env, &config, &mut market_state, &resolution.resolver);
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(Response::new().add_messages(bounty_msg).add_event(
+        Event::new("cc_prediction_market_finalize_resolution")
+            .add_attribute("market_id", config.id)
+            .add_attribute("winning_option", winning_option.text)
+            .add_attribute("resolver", resolution.resolver.to_string())
+            .add_attribute("bounty", bounty.to_string()),
+    ))
+}
+
+/// Latest price of `asset` on the market's oracle, with the time it was produced
+fn query_oracle_price(
+    deps: Deps,
+    config: &Config,
+    asset: &str,
+) -> Result<Option<PriceSnapshot>, ContractError> {
+    let source = OracleSource::from_str(&config.resolution_source)?;
+    query_source_price(deps, &source, &config.oracle, asset)
+}
+
+/// Latest price of `asset` on `oracle`, through the adapter selected by the resolution source
+fn query_source_price(
+    deps: Deps,
+    source: &OracleSource,
+    oracle: &Addr,
+    asset: &str,
+) -> Result<Option<PriceSnapshot>, ContractError> {
+    let adapter: Box<dyn PriceOracle> = match source {
+        OracleSource::ClpFeed => Box::new(ClpFeedOracle {
+            feed: oracle.clone(),
+        }),
+        OracleSource::Fixed { .. } => Box::new(FixedPriceOracle {
+            price: MANUAL_PRICES
+                .may_load(deps.storage, asset)?
+                .map(|manual| OraclePrice {
+                    price: manual.price,
+                    timestamp: manual.timestamp,
+                }),
+        }),
+        OracleSource::WasmQuery(source) => Box::new(WasmQueryOracle {
+            contract: oracle.clone(),
+            source: source.clone(),
+        }),
+    };
+
+    Ok(adapter
+        .query_price(&deps.querier, asset)?
+        .map(|price| PriceSnapshot {
+            price: price.price,
+            timestamp: price.timestamp,
+        }))
+}
+
+/// Settlement price of `asset`: the oracle price closest to, and not after, end_time.
+/// Either the latest feed price (if not newer than end_time) or the keeper snapshot in `snapshots`
+fn settle_price(
+    deps: &mut DepsMut,
+    config: &Config,
+    asset: &str,
+    snapshots: &Item<PriceSnapshot>,
+) -> Result<PriceSnapshot, ContractError> {
+    // With an oracle quorum the median of all feeds replaces the single feed and its snapshots
+    if let Some(quorum) = &config.oracle_quorum {
+        let settlement = query_median_price(deps.as_ref(), config, quorum, asset)?;
+        snapshots.save(deps.storage, &settlement)?;
+        return Ok(settlement);
+    }
+
+    // With a TWAP window the average of the keeper observations replaces the single price
+    if config.twap_window.is_some() {
+        // The latest feed price counts as one more observation
+        if let Some(latest) = query_oracle_price(deps.as_ref(), config, asset)? {
+            record_twap_observation(deps.storage, config, asset, &latest)?;
+        }
+        let settlement =
+            twap_price(deps.storage, config, asset)?.ok_or(ContractError::NoSettlementPrice {
+                end_time: config.end_time,
+            })?;
+        ensure_price_fresh(config, &settlement)?;
+        snapshots.save(deps.storage, &settlement)?;
+        return Ok(settlement);
+    }
+
+    let latest = query_oracle_price(deps.as_ref(), config, asset)?
+        .filter(|snapshot| snapshot.timestamp <= config.end_time);
+    let snapshot = snapshots.may_load(deps.storage)?;
+
+    let settlement = match (latest, snapshot) {
+        (Some(latest), Some(snapshot)) if snapshot.timestamp > latest.timestamp => snapshot,
+        (Some(latest), _) => latest,
+        (None, Some(snapshot)) => snapshot,
+        (None, None) => {
+            return Err(ContractError::NoSettlementPrice {
+                end_time: config.end_time,
+            })
+        }
+    };
+    ensure_price_fresh(config, &settlement)?;
+    snapshots.save(deps.storage, &settlement)?;
+
+    Ok(settlement)
+}
+
+/// Records `price` as a TWAP observation of `asset`. The latest price before the closing
+/// window opens it, and each bucket of the window keeps its latest price.
+/// Returns whether it was recorded
+fn record_twap_observation(
+    storage: &mut dyn Storage,
+    config: &Config,
+    asset: &str,
+    price: &PriceSnapshot,
+) -> Result<bool, ContractError> {
+    let Some(twap_window) = config.twap_window else {
+        return Ok(false);
+    };
+    let window_start = config.end_time.minus_seconds(twap_window).nanos();
+    let time = price.timestamp.nanos();
+    if time > config.end_time.nanos() {
+        return Ok(false);
+    }
+
+    // Nanosecond range of the slot `price` falls in, only the latest observation of a slot is kept
+    let (slot_start, slot_end) = if time < window_start {
+        (0, window_start)
+    } else {
+        let bucket = (twap_window * 1_000_000_000 / MAX_TWAP_OBSERVATIONS as u64).max(1);
+        let last = MAX_TWAP_OBSERVATIONS as u64 - 1;
+        let index = ((time - window_start) / bucket).min(last);
+        let slot_start = window_start + index * bucket;
+        // The last bucket runs to end_time, whatever the rounding of the bucket size
+        if index == last {
+            (slot_start, config.end_time.nanos() + 1)
+        } else {
+            (slot_start, slot_start + bucket)
+        }
+    };
+    let slot = TWAP_OBSERVATIONS
+        .prefix(asset)
+        .keys(
+            storage,
+            Some(Bound::inclusive(slot_start)),
+            Some(Bound::exclusive(slot_end)),
+            cosmwasm_std::Order::Descending,
+        )
+        .collect::<StdResult<Vec<u64>>>()?;
+    if slot.first().is_some_and(|latest| *latest >= time) {
+        return Ok(false);
+    }
+
+    for replaced in slot {
+        TWAP_OBSERVATIONS.remove(storage, (asset, replaced));
+    }
+    TWAP_OBSERVATIONS.save(storage, (asset, time), &price.price)?;
+
+    Ok(true)
+}
+
+/// Time-weighted average of the observations of `asset` over the closing window.
+/// The first observation holds from the start of the window, each one until the next
+/// observation and the last one until end_time. Timestamped with the last observation
+pub fn twap_price(
+    storage: &dyn Storage,
+    config: &Config,
+    asset: &str,
+) -> StdResult<Option<PriceSnapshot>> {
+    let observations = TWAP_OBSERVATIONS
+        .prefix(asset)
+        .range(storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<(u64, Decimal)>>>()?;
+    let Some((last_time, last_price)) = observations.last().copied() else {
+        return Ok(None);
+    };
+    let window_start = config
+        .end_time
+        .minus_seconds(config.twap_window.unwrap_or_default())
+        .nanos();
+
+    let mut weighted_sum = Decimal::zero();
+    let mut total_seconds = 0u64;
+    for (index, (time, price)) in observations.iter().enumerate() {
+        let from = if index == 0 { window_start } else { *time };
+        let until = observations
+            .get(index + 1)
+            .map(|(next, _)| *next)
+            .unwrap_or(config.end_time.nanos());
+        let seconds = until.saturating_sub(from) / 1_000_000_000;
+        weighted_sum += *price * Decimal::from_ratio(seconds, 1u128);
+        total_seconds += seconds;
+    }
+
+    // Observations all in the last second before end_time have no weight, the last one is used
+    let price = if total_seconds == 0 {
+        last_price
+    } else {
+        weighted_sum / Decimal::from_ratio(total_seconds, 1u128)
+    };
+
+    Ok(Some(PriceSnapshot {
+        price,
+        timestamp: Timestamp::from_nanos(last_time),
+    }))
+}
+
+/// Median price of `asset` across the quorum feeds, not after end_time.
+/// Missing and stale prices are dropped, and the median is only used if at least
+/// `min_responses` feeds are within `max_deviation` of it
+fn query_median_price(
+    deps: Deps,
+    config: &Config,
+    quorum: &OracleQuorum,
+    asset: &str,
+) -> Result<PriceSnapshot, ContractError> {
+    let source = OracleSource::from_str(&config.resolution_source)?;
+    let mut responses: Vec<PriceSnapshot> = Vec::new();
+    let mut last_stale_update: Option<Timestamp> = None;
+    for feed in quorum.oracles.iter() {
+        let Some(price) = quorum_feed_price(deps, config, &source, feed, asset)? else {
+            continue;
+        };
+        if ensure_price_fresh(config, &price).is_err() {
+            last_stale_update = last_stale_update.max(Some(price.timestamp));
+            continue;
+        }
+        responses.push(price);
+    }
+
+    if (responses.len() as u32) < quorum.min_responses || responses.is_empty() {
+        let error = match last_stale_update {
+            Some(last_update) => UtilsError::PriceStale {
+                last_update: last_update.to_string(),
+            },
+            None => UtilsError::PriceOracleError {
+                reason: format!(
+                    "{} of {} required oracles responded",
+                    responses.len(),
+                    quorum.min_responses
+                ),
+            },
+        };
+        return Err(error.into());
+    }
+
+    let mut prices: Vec<Decimal> = responses.iter().map(|response| response.price).collect();
+    let median_price = median(&mut prices).ok_or_else(|| {
+        ContractError::Std(StdError::generic_err("No price available from oracle"))
+    })?;
+
+    // Relative distance of each response to the median
+    let deviations: Vec<Decimal> = responses
+        .iter()
+        .map(|response| {
+            response
+                .price
+                .abs_diff(median_price)
+                .checked_div(median_price)
+                .unwrap_or(Decimal::MAX)
+        })
+        .collect();
+
+    let agreeing: Vec<&PriceSnapshot> = responses
+        .iter()
+        .zip(deviations.iter())
+        .filter(|(_, deviation)| **deviation <= quorum.max_deviation)
+        .map(|(response, _)| response)
+        .collect();
+
+    if (agreeing.len() as u32) < quorum.min_responses {
+        let worst = deviations.iter().max().copied().unwrap_or_default();
+        return Err(UtilsError::PriceDeviationTooHigh {
+            deviation: worst
+                .checked_mul(Decimal::percent(10000))
+                .unwrap_or(Decimal::MAX)
+                .to_string(),
+        }
+        .into());
+    }
+
+    // The median is only as recent as the oldest price it relies on
+    let timestamp = agreeing
+        .iter()
+        .map(|response| response.timestamp)
+        .min()
+        .unwrap_or(config.end_time);
+
+    Ok(PriceSnapshot {
+        price: median_price,
+        timestamp,
+    })
+}
+
+/// Price of `asset` on the quorum `feed` closest to, and not after, end_time.
+/// Either its latest price or the keeper snapshot, a zero price counts as missing
+fn quorum_feed_price(
+    deps: Deps,
+    config: &Config,
+    source: &OracleSource,
+    feed: &Addr,
+    asset: &str,
+) -> StdResult<Option<PriceSnapshot>> {
+    // A feed that fails to answer is treated like a missing price
+    let latest = match query_source_price(deps, source, feed, asset) {
+        Ok(Some(price)) if price.timestamp <= config.end_time && !price.price.is_zero() => {
+            Some(price)
+        }
+        _ => None,
+    };
+    let snapshot = QUORUM_SNAPSHOTS.may_load(deps.storage, (asset, feed))?;
+
+    Ok(match (latest, snapshot) {
+        (Some(latest), Some(snapshot)) if snapshot.timestamp > latest.timestamp => Some(snapshot),
+        (Some(latest), _) => Some(latest),
+        (None, snapshot) => snapshot,
+    })
+}
+
+/// Rejects settlement prices older than the configured max staleness at end_time
+fn ensure_price_fresh(config: &Config, snapshot: &PriceSnapshot) -> Result<(), ContractError> {
+    if let Some(max_staleness) = config.max_price_staleness {
+        if snapshot.timestamp.plus_seconds(max_staleness) < config.end_time {
+            return Err(ContractError::PriceTooStale {
+                price_time: snapshot.timestamp,
+                end_time: config.end_time,
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn set_manual_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    asset: String,
+    price: Decimal,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let market_state = MARKET_STATE.load(deps.storage)?;
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !matches!(
+        OracleSource::from_str(&config.resolution_source)?,
+        OracleSource::Fixed { .. }
+    ) {
+        return Err(ContractError::NotManualPriceMarket {});
+    }
+
+    if asset != config.asset_to_track && Some(&asset) != config.second_asset_to_track.as_ref() {
+        return Err(ContractError::UntrackedAsset { asset });
+    }
+
+    if price.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Price must be positive",
+        )));
+    }
+
+    ensure_not_settled(&market_state)?;
+
+    // A price posted after close was seen after close, it can't be the closing price
+    if env.block.time > config.end_time {
+        return Err(ContractError::PriceAfterEndTime {
+            price_time: env.block.time,
+            end_time: config.end_time,
+        });
+    }
+
+    let manual_price = PriceSnapshot {
+        price,
+        timestamp: env.block.time,
+    };
+    MANUAL_PRICES.save(deps.storage, &asset, &manual_price)?;
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_set_manual_price")
+            .add_attribute("market_id", config.id)
+            .add_attribute("asset", asset)
+            .add_attribute("price", price.to_string())
+            .add_attribute("price_timestamp", manual_price.timestamp.to_string())
+            .add_attribute("user", info.sender.to_string()),
+    ))
+}
+
+pub fn snapshot_price(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let market_state = MARKET_STATE.load(deps.storage)?;
+
+    ensure_not_settled(&market_state)?;
+
+    if !config.market_type.is_price_based() {
+        return Err(ContractError::NotPriceMarket {});
+    }
+
+    let (latest, updated) =
+        snapshot_asset_price(&mut deps, &config, &config.asset_to_track, &SETTLEMENT_PRICE)?;
+
+    let mut event = Event::new("cc_prediction_market_snapshot_price")
+        .add_attribute("market_id", config.id.clone())
+        .add_attribute("price", latest.price.to_string())
+        .add_attribute("price_timestamp", latest.timestamp.to_string())
+        .add_attribute("updated", updated.to_string())
+        .add_attribute("user", info.sender.to_string());
+
+    // Relative markets settle on both assets
+    if let Some(second_asset) = &config.second_asset_to_track {
+        let (second, second_updated) =
+            snapshot_asset_price(&mut deps, &config, second_asset, &SECOND_SETTLEMENT_PRICE)?;
+        event = event
+            .add_attribute("second_price", second.price.to_string())
+            .add_attribute("second_price_timestamp", second.timestamp.to_string())
+            .add_attribute("second_updated", second_updated.to_string());
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Saves the latest price of `asset` in `snapshots` if it is the closest yet to end_time,
+/// and as a TWAP observation if it is in the closing window.
+/// Returns the latest price and whether it was saved
+fn snapshot_asset_price(
+    deps: &mut DepsMut,
+    config: &Config,
+    asset: &str,
+    snapshots: &Item<PriceSnapshot>,
+) -> Result<(PriceSnapshot, bool), ContractError> {
+    // Quorum markets keep a snapshot of each feed, the median is taken at settlement
+    if let Some(quorum) = &config.oracle_quorum {
+        return snapshot_quorum_prices(deps, config, quorum, asset);
+    }
+
+    let latest = query_oracle_price(deps.as_ref(), config, asset)?.ok_or_else(|| {
+        ContractError::Std(StdError::generic_err("No price available from oracle"))
+    })?;
+
+    if latest.timestamp > config.end_time {
+        return Err(ContractError::PriceAfterEndTime {
+            price_time: latest.timestamp,
+            end_time: config.end_time,
+        });
+    }
+
+    let observed = record_twap_observation(deps.storage, config, asset, &latest)?;
+
+    // Only keep the snapshot closest to end_time
+    let existing = snapshots.may_load(deps.storage)?;
+    let updated = match &existing {
+        Some(existing) => latest.timestamp > existing.timestamp,
+        None => true,
+    };
+    if updated {
+        snapshots.save(deps.storage, &latest)?;
+    }
+
+    Ok((latest, updated || observed))
+}
+
+/// Saves the latest price of each quorum feed that is the closest yet to end_time.
+/// Returns the median of the latest prices and whether any snapshot was saved
+fn snapshot_quorum_prices(
+    deps: &mut DepsMut,
+    config: &Config,
+    quorum: &OracleQuorum,
+    asset: &str,
+) -> Result<(PriceSnapshot, bool), ContractError> {
+    let source = OracleSource::from_str(&config.resolution_source)?;
+    let mut prices: Vec<Decimal> = Vec::new();
+    let mut oldest: Option<Timestamp> = None;
+    let mut updated = false;
+    for feed in quorum.oracles.iter() {
+        let latest = match query_source_price(deps.as_ref(), &source, feed, asset) {
+            Ok(Some(price)) if price.timestamp <= config.end_time && !price.price.is_zero() => {
+                price
+            }
+            _ => continue,
+        };
+
+        let existing = QUORUM_SNAPSHOTS.may_load(deps.storage, (asset, feed))?;
+        if existing.is_none_or(|existing| latest.timestamp > existing.timestamp) {
+            QUORUM_SNAPSHOTS.save(deps.storage, (asset, feed), &latest)?;
+            updated = true;
+        }
+        oldest = Some(oldest.map_or(latest.timestamp, |oldest| oldest.min(latest.timestamp)));
+        prices.push(latest.price);
+    }
+
+    let price = median(&mut prices).ok_or_else(|| {
+        ContractError::Std(StdError::generic_err("No price available from oracle"))
+    })?;
+
+    Ok((
+        PriceSnapshot {
+            price,
+            timestamp: oldest.unwrap_or(config.end_time),
+        },
+        updated,
+    ))
+}
+
+/// Records the strike of an UpDown or Relative market, the oracle price closest to start_time.
+/// Before start_time every call moves the strike to the latest price, from start_time on it is final.
+/// Returns the newly recorded strike, None if the market has no strike or it is unchanged
+fn record_strike_price(
+    deps: &mut DepsMut,
+    config: &Config,
+    now: Timestamp,
+) -> Result<Option<PriceSnapshot>, ContractError> {
+    if !config.market_type.has_strike() {
+        return Ok(None);
+    }
+    let existing = STRIKE_PRICE.may_load(deps.storage)?;
+    if existing.is_some() && now >= config.start_time {
+        return Ok(None);
+    }
+
+    let strike = strike_snapshot(deps.as_ref(), config, &config.asset_to_track)?;
+    if existing.is_some_and(|existing| strike.timestamp <= existing.timestamp) {
+        return Ok(None);
+    }
+    STRIKE_PRICE.save(deps.storage, &strike)?;
+
+    // Both assets of a Relative market are struck at the same time
+    if let Some(second_asset) = &config.second_asset_to_track {
+        let second_strike = strike_snapshot(deps.as_ref(), config, second_asset)?;
+        SECOND_STRIKE_PRICE.save(deps.storage, &second_strike)?;
+    }
+
+    Ok(Some(strike))
+}
+
+/// Latest oracle price of `asset`, if it is close enough to start_time to be a strike
+fn strike_snapshot(
+    deps: Deps,
+    config: &Config,
+    asset: &str,
+) -> Result<PriceSnapshot, ContractError> {
+    let latest = query_oracle_price(deps, config, asset)?.ok_or_else(|| {
+        ContractError::Std(StdError::generic_err("No price available from oracle"))
+    })?;
+    ensure_valid_strike(config, &latest)?;
+    Ok(latest)
+}
+
+/// Rejects zero strike prices, performance is measured relative to the strike,
+/// and strike prices more than STRIKE_PRICE_TOLERANCE away from start_time
+fn ensure_valid_strike(config: &Config, strike: &PriceSnapshot) -> Result<(), ContractError> {
+    if strike.price.is_zero() {
+        return Err(ContractError::ZeroStrikePrice {});
+    }
+
+    let earliest = config.start_time.minus_seconds(STRIKE_PRICE_TOLERANCE);
+    let latest = config.start_time.plus_seconds(STRIKE_PRICE_TOLERANCE);
+    if strike.timestamp < earliest || strike.timestamp > latest {
+        return Err(ContractError::StrikePriceOutOfTolerance {
+            price_time: strike.timestamp,
+            start_time: config.start_time,
+        });
+    }
+    Ok(())
+}
+
+pub fn start_market(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    if !config.market_type.has_strike() {
+        return Err(ContractError::NoStrikeForMarketType {});
+    }
+
+    // The strike can be taken from STRIKE_PRICE_TOLERANCE before start_time until end_time
+    let opens_at = config.start_time.minus_seconds(STRIKE_PRICE_TOLERANCE);
+    if market_state.status != MarketStatus::Pending {
+        ensure_market_active(&market_state, &config)?;
+    } else if env.block.time < opens_at {
+        return Err(ContractError::MarketNotStarted {
+            start_time: opens_at,
+        });
+    }
+
+    let recorded = record_strike_price(&mut deps, &config, env.block.time)?;
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    let strike = STRIKE_PRICE.load(deps.storage)?;
+
+    let mut event = Event::new("cc_prediction_market_start_market")
+        .add_attribute("market_id", config.id)
+        .add_attribute("strike_price", strike.price.to_string())
+        .add_attribute("price_timestamp", strike.timestamp.to_string())
+        .add_attribute("updated", recorded.is_some().to_string())
+        .add_attribute("user", info.sender.to_string());
+
+    if let Some(second_strike) = SECOND_STRIKE_PRICE.may_load(deps.storage)? {
+        event = event
+            .add_attribute("second_strike_price", second_strike.price.to_string())
+            .add_attribute("second_price_timestamp", second_strike.timestamp.to_string());
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Whether `price` touches the barrier of a Touch market, approached from the initial price
+fn barrier_touched(config: &Config, price: Decimal) -> bool {
+    if config.initial_price <= config.target_price {
+        price >= config.target_price
+    } else {
+        price <= config.target_price
+    }
+}
+
+/// Trading stops once the barrier of a Touch market is hit, the outcome is known
+fn ensure_barrier_not_hit(deps: Deps) -> Result<(), ContractError> {
+    match BARRIER_HIT.may_load(deps.storage)? {
+        Some(hit) => Err(ContractError::BarrierHit {
+            price_time: hit.timestamp,
+        }),
+        None => Ok(()),
+    }
+}
+
+pub fn observe_price(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let market_state = MARKET_STATE.load(deps.storage)?;
+
+    if config.market_type != MarketType::Touch {
+        return Err(ContractError::NoBarrierForMarketType {});
+    }
+
+    ensure_not_settled(&market_state)?;
+    ensure_barrier_not_hit(deps.as_ref())?;
+
+    let latest = query_oracle_price(deps.as_ref(), &config, &config.asset_to_track)?.ok_or_else(|| {
+        ContractError::Std(StdError::generic_err("No price available from oracle"))
+    })?;
+
+    // Only prices produced during the trading window count
+    if latest.timestamp < config.start_time {
+        return Err(ContractError::MarketNotStarted {
+            start_time: config.start_time,
+        });
+    }
+    if latest.timestamp > config.end_time {
+        return Err(ContractError::PriceAfterEndTime {
+            price_time: latest.timestamp,
+            end_time: config.end_time,
+        });
+    }
+
+    let touched = barrier_touched(&config, latest.price);
+    if touched {
+        BARRIER_HIT.save(deps.storage, &latest)?;
+    }
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_observe_price")
+            .add_attribute("market_id", config.id)
+            .add_attribute("price", latest.price.to_string())
+            .add_attribute("price_timestamp", latest.timestamp.to_string())
+            .add_attribute("target_price", config.target_price.to_string())
+            .add_attribute("touched", touched.to_string())
+            .add_attribute("user", info.sender.to_string()),
+    ))
+}
+
+pub fn dispute_resolution(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+
+    let dispute_config = config
+        .dispute_config
+        .clone()
+        .ok_or(ContractError::DisputesNotEnabled {})?;
+
+    let proposed_option = match &market_state.status {
+        MarketStatus::ProposedResolution {
+            option,
+            challenge_ends,
+        } => {
+            if env.block.time >= *challenge_ends {
+                return Err(ContractError::ChallengePeriodEnded {
+                    challenge_ends: *challenge_ends,
+                });
+            }
+            option.clone()
+        }
+        _ => return Err(ContractError::NoProposedResolution {}),
+    };
+
+    let bond = must_pay(&info, &config.buy_token)?;
+    if bond != dispute_config.bond {
+        return Err(ContractError::InvalidDisputeBond {
+            required: dispute_config.bond,
+            sent: bond,
+        });
+    }
+
+    let dispute = Dispute {
+        challenger: info.sender.clone(),
+        bond,
+        proposed_option: proposed_option.clone(),
+        disputed_at: env.block.time,
+        ruling: None,
+    };
+    DISPUTE.save(deps.storage, &dispute)?;
+
+    // Withdrawals stay closed until the arbitrator rules
+    market_state.status = MarketStatus::Disputed(proposed_option.clone());
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_dispute")
+            .add_attribute("market_id", config.id)
+            .add_attribute("proposed_option", proposed_option.text)
+            .add_attribute("challenger", info.sender.to_string())
+            .add_attribute("bond", bond.to_string())
+            .add_attribute("arbitrator", dispute_config.arbitrator.to_string()),
+    ))
+}
+
+pub fn arbitrate_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    winning_option: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+
+    let dispute_config = config
+        .dispute_config
+        .clone()
+        .ok_or(ContractError::DisputesNotEnabled {})?;
+
+    if info.sender != dispute_config.arbitrator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let ruling = config
+        .pairs
+        .iter()
+        .find(|p| p.text == winning_option)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("Invalid option"))?;
+
+    let proposed_option = match &market_state.status {
+        MarketStatus::Disputed(option) => option.clone(),
+        _ => return Err(ContractError::NotDisputed {}),
+    };
+    let upheld = proposed_option == ruling;
+
+    let mut event = Event::new("cc_prediction_market_arbitrate")
+        .add_attribute("market_id", config.id.clone())
+        .add_attribute("winning_option", ruling.text.clone())
+        .add_attribute("upheld", upheld.to_string());
+    let mut response = Response::new();
+
+    // Markets disputed by conflicting resolver votes have no challenger bond
+    let dispute = DISPUTE.may_load(deps.storage)?;
+    if let Some(mut dispute) = dispute.clone() {
+        // Upheld: the challenger was wrong and the bond is slashed to the admin.
+        // Overturned: the bond is returned to the challenger.
+        let bond_recipient = if upheld {
+            config.admin.clone()
+        } else {
+            dispute.challenger.clone()
+        };
+
+        dispute.ruling = Some(ruling.clone());
+        DISPUTE.save(deps.storage, &dispute)?;
+
+        let bond_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: bond_recipient.to_string(),
+            amount: vec![Coin {
+                denom: config.buy_token.clone(),
+                amount: dispute.bond.to_string(),
+            }],
+        };
+        response = response.add_message(CosmosMsg::Any(bond_msg.to_any()));
+        event = event
+            .add_attribute("bond_recipient", bond_recipient.to_string())
+            .add_attribute("bond", dispute.bond.to_string());
+    }
+
+    // A scalar market ruled against its price pays the ruled side the whole pot
+    let ruled_scalar_payout = || {
+        if ruling == config.pairs[0] {
+            Decimal::one()
+        } else {
+            Decimal::zero()
+        }
+    };
+
+    let resolution = match RESOLUTION.may_load(deps.storage)? {
+        Some(mut resolution) => {
+            if !upheld && resolution.scalar_payout.is_some() {
+                resolution.scalar_payout = Some(ruled_scalar_payout());
+            }
+            resolution.winning_option = ruling.clone();
+            resolution
+        }
+        // Conflicting resolver votes never produced a resolution, the ruling is the resolution.
+        // Members voting on a price market already saved the settlement price
+        None => {
+            let settlement = match BARRIER_HIT.may_load(deps.storage)? {
+                Some(hit) => Some(hit),
+                None => SETTLEMENT_PRICE.may_load(deps.storage)?,
+            };
+            let target_price = match config.market_type {
+                MarketType::UpDown | MarketType::Relative => STRIKE_PRICE
+                    .may_load(deps.storage)?
+                    .map_or(config.target_price, |strike| strike.price),
+                _ => config.target_price,
+            };
+            let scalar_payout = settlement.as_ref().and_then(|settlement| {
+                let price_winner = config
+                    .market_type
+                    .determine_winner(settlement.price, target_price);
+                agreed_scalar_payout(&config, &ruling, &price_winner, settlement.price)
+            });
+
+            ResolutionRecord {
+                winning_option: ruling.clone(),
+                price: settlement
+                    .as_ref()
+                    .map(|settlement| settlement.price)
+                    .unwrap_or_default(),
+                price_timestamp: settlement
+                    .as_ref()
+                    .map_or(env.block.time, |settlement| settlement.timestamp),
+                oracle: config.oracle.clone(),
+                resolver: info.sender.clone(),
+                block_height: env.block.height,
+                resolved_at: env.block.time,
+                initial_price: config.initial_price,
+                target_price,
+                scalar_payout,
+                second_price: SECOND_SETTLEMENT_PRICE
+                    .may_load(deps.storage)?
+                    .map(|settlement| settlement.price),
+            }
+        }
+    };
+    RESOLUTION.save(deps.storage, &resolution)?;
+
+    // The ruling is final, the bounty goes to whoever got the outcome right
+    let bounty_recipient = match dispute {
+        Some(dispute) if !upheld => dispute.challenger,
+        _ => resolution.resolver.clone(),
+    };
+    let (bounty_msg, bounty) = take_bounty(&env, &config, &mut market_state, &bounty_recipient);
+    event = event
+        .add_attribute("bounty_recipient", bounty_recipient.to_string())
+        .add_attribute("bounty", bounty.to_string());
+
+    market_state.status = MarketStatus::Resolved(ruling);
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(response.add_messages(bounty_msg).add_event(event))
+}
+
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    // Get winning option (also checks if market is resolved and final)
+    let winning_option = match &market_state.status {
+        MarketStatus::Resolved(option) => option,
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Market is not resolved yet",
+            )))
+        }
+    };
+
+    // Scalar markets pay both sides, each side returned is settled on its own
+    let paid_options: Vec<MarketOption> = match config.market_type {
+        MarketType::Scalar { .. } => config
+            .pairs
+            .iter()
+            .filter(|p| {
+                info.funds
+                    .iter()
+                    .any(|coin| coin.denom == p.associated_token_denom)
+            })
+            .cloned()
+            .collect(),
+        _ => vec![winning_option.clone()],
+    };
+
+    // Only the paid tokens can be returned
+    if info.funds.is_empty() {
+        return Err(ContractError::Payment(PaymentError::NoFunds {}));
+    }
+    if let Some(extra) = info.funds.iter().find(|coin| {
+        !paid_options
+            .iter()
+            .any(|p| p.associated_token_denom == coin.denom)
+    }) {
+        return Err(ContractError::Payment(PaymentError::ExtraDenom(
+            extra.denom.clone(),
+        )));
+    }
+
+    // The paid positions are returned as outcome tokens and burned,
+    // so tokens backing complete sets are never paid a second time
+    let mut burn_msgs: Vec<CosmosMsg> = vec![];
+    let mut paid_tokens = vec![Uint128::zero(); config.pairs.len()];
+    for option in paid_options.iter() {
+        // Load and check user's winning share using Map - O(1) operation
+        let share = SHARES
+            .may_load(deps.storage, (&info.sender, &option.text))?
+            .ok_or_else(|| StdError::generic_err("No winning shares found for user"))?;
+
+        if share.has_withdrawn {
+            return Err(ContractError::Std(StdError::generic_err(
+                "User has already withdrawn their winnings",
+            )));
+        }
+
+        let returned = info
+            .funds
+            .iter()
+            .find(|coin| coin.denom == option.associated_token_denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+        // Any part of the position can be returned, it is paid pro rata
+        if returned > share.amount {
+            return Err(ContractError::WithdrawExceedsShares {
+                option: option.text.clone(),
+                available: share.amount,
+            });
+        }
+        let burn_msg = MsgBurn {
+            sender: env.contract.address.to_string(),
+            coin: Some(Coin {
+                denom: option.associated_token_denom.clone(),
+                amount: returned.to_string(),
+            }),
+        };
+        burn_msgs.push(CosmosMsg::Any(burn_msg.to_any()));
+        if let Some(index) = config.pairs.iter().position(|p| p.text == option.text) {
+            paid_tokens[index] = returned;
+        }
+
+        // Paid tokens are no longer owed by the house
+        if let Some(positions) = house_positions(&config.pricing_mode) {
+            positions.update(
+                deps.storage,
+                &option.text,
+                |quantity| -> StdResult<Uint128> {
+                    Ok(quantity.unwrap_or_default().saturating_sub(returned))
+                },
+            )?;
+        }
+        update_share_total(deps.storage, &option.text, Uint128::zero(), returned)?;
+
+        // The share is withdrawn once all of its tokens are returned
+        let remaining = share.amount - returned;
+        SHARES.save(
+            deps.storage,
+            (&info.sender, &option.text),
+            &Share {
+                amount: remaining,
+                has_withdrawn: remaining.is_zero(),
+            },
+        )?;
+    }
+
+    let total_winnings = market_state.resolved_payout(deps.storage, &config, &paid_tokens)?;
+
+    // LMSR and fixed-odds winnings come out of the house funds
+    if config.pricing_mode != PricingMode::Parimutuel {
+        market_state.total_value.amount = Uint128::from_str(&market_state.total_value.amount)?
+            .saturating_sub(total_winnings)
+            .to_string();
+        save_market_state(deps.storage, &market_state)?;
+    }
+
+    let mut response = Response::new().add_messages(burn_msgs);
+
+    // A Scalar side can be worth nothing
+    if !total_winnings.is_zero() {
+        // Create bank transfer message
+        let transfer_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                amount: total_winnings.to_string(),
+                denom: config.buy_token.clone(),
+            }],
+        };
+        response = response.add_message(CosmosMsg::Any(transfer_msg.to_any()));
+    }
+
+    Ok(response.add_event(
+        Event::new("cc_prediction_market_withdraw")
+            .add_attribute("market_id", market_id)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("winning_option", winning_option.text.clone())
+            .add_attribute("total_winnings", total_winnings.to_string()),
+    ))
+}
+
+pub fn mint_complete_set(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    ensure_not_settled(&market_state)?;
+
+    let amount = must_pay(&info, &config.buy_token)?;
+
+    let outstanding = COMPLETE_SETS.may_load(deps.storage)?.unwrap_or_default() + amount;
+    COMPLETE_SETS.save(deps.storage, &outstanding)?;
+
+    // One of every outcome token per buy_token paid
+    let mint_msgs: Vec<CosmosMsg> = config
+        .pairs
+        .iter()
+        .map(|option| {
+            let mint_msg = MsgMint {
+                sender: env.contract.address.to_string(),
+                coin: Some(Coin {
+                    denom: option.associated_token_denom.clone(),
+                    amount: amount.to_string(),
+                }),
+                recipient: info.sender.to_string(),
+            };
+            CosmosMsg::Any(mint_msg.to_any())
+        })
+        .collect();
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_mint_complete_set")
+                .add_attribute("market_id", config.id)
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("complete_sets", outstanding.to_string()),
+        )
+        .add_messages(mint_msgs))
+}
+
+pub fn redeem_complete_set(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    if info.funds.is_empty() {
+        return Err(ContractError::Payment(PaymentError::NoFunds {}));
+    }
+
+    let returned = |option: &MarketOption| -> Uint128 {
+        info.funds
+            .iter()
+            .find(|coin| coin.denom == option.associated_token_denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default()
+    };
+    if let Some(invalid) = info.funds.iter().find(|coin| {
+        !config
+            .pairs
+            .iter()
+            .any(|p| p.associated_token_denom == coin.denom)
+    }) {
+        return Err(ContractError::Payment(PaymentError::ExtraDenom(
+            invalid.denom.clone(),
+        )));
+    }
+
+    // Once resolved, winning tokens alone are worth one buy_token and losing tokens nothing.
+    // Scalar markets pay both sides, so only complete sets are redeemed there
+    let amount = match (&market_state.status, &config.market_type) {
+        (MarketStatus::Resolved(winning_option), market_type)
+            if !matches!(market_type, MarketType::Scalar { .. }) =>
+        {
+            returned(winning_option)
+        }
+        _ => {
+            let amount = returned(&config.pairs[0]);
+            if config.pairs.iter().any(|option| returned(option) != amount) {
+                return Err(ContractError::InvalidCompleteSet {});
+            }
+            amount
+        }
+    };
+    if amount.is_zero() {
+        return Err(ContractError::InvalidCompleteSet {});
+    }
+
+    // The collateral is fungible, any holder redeems up to the outstanding complete sets
+    let outstanding = COMPLETE_SETS.may_load(deps.storage)?.unwrap_or_default();
+    if amount > outstanding {
+        return Err(ContractError::InsufficientCompleteSets { outstanding });
+    }
+    COMPLETE_SETS.save(deps.storage, &(outstanding - amount))?;
+
+    let mut messages: Vec<CosmosMsg> = info
+        .funds
+        .iter()
+        .map(|returned| {
+            let burn_msg = MsgBurn {
+                sender: env.contract.address.to_string(),
+                coin: Some(Coin {
+                    denom: returned.denom.clone(),
+                    amount: returned.amount.to_string(),
+                }),
+            };
+            CosmosMsg::Any(burn_msg.to_any())
+        })
+        .collect();
+
+    let redeem_msg = MsgSend {
+        from_address: env.contract.address.to_string(),
+        to_address: info.sender.to_string(),
+        amount: vec![Coin {
+            denom: config.buy_token.clone(),
+            amount: amount.to_string(),
+        }],
+    };
+    messages.push(CosmosMsg::Any(redeem_msg.to_any()));
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_redeem_complete_set")
+                .add_attribute("market_id", config.id)
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("complete_sets", (outstanding - amount).to_string()),
+        )
+        .add_messages(messages))
+}
+
+pub fn fund_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    ensure_not_settled(&market_state)?;
+    let liquidity = match config.pricing_mode {
+        PricingMode::Lmsr { liquidity } => liquidity,
+        PricingMode::FixedOdds => return fund_bankroll(deps, info, config, market_state),
+        PricingMode::Parimutuel => return Err(ContractError::NoHouseLiquidity {}),
+    };
+    if LMSR_SUBSIDY.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::LmsrAlreadyFunded {});
+    }
+
+    // The subsidy covers the worst case loss of the market maker
+    let required = lmsr_required_subsidy(&config, liquidity)?;
+    let sent = must_pay(&info, &config.buy_token)?;
+    if sent != required {
+        return Err(ContractError::InvalidLiquidityFunding { required, sent });
+    }
+    LMSR_SUBSIDY.save(deps.storage, &sent)?;
+
+    market_state.total_value.amount =
+        (Uint128::from_str(&market_state.total_value.amount)? + sent).to_string();
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_fund_liquidity")
+            .add_attribute("market_id", config.id)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("liquidity", liquidity.to_string())
+            .add_attribute("subsidy", sent.to_string()),
+    ))
+}
+
+/// Adds the payment to the bankroll of a fixed-odds market, any amount at any time before settlement
+fn fund_bankroll(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Config,
+    mut market_state: MarketState,
+) -> Result<Response, ContractError> {
+    let amount = must_pay(&info, &config.buy_token)?;
+    let bankroll = BANKROLL.may_load(deps.storage)?.unwrap_or_default() + amount;
+    BANKROLL.save(deps.storage, &bankroll)?;
+
+    market_state.total_value.amount =
+        (Uint128::from_str(&market_state.total_value.amount)? + amount).to_string();
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_fund_bankroll")
+            .add_attribute("market_id", config.id)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("bankroll", bankroll.to_string()),
+    ))
+}
+
+pub fn set_house_line(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    option: String,
+    odds: Decimal,
+    liability_cap: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.pricing_mode != PricingMode::FixedOdds {
+        return Err(ContractError::NotFixedOddsMarket {});
+    }
+    ensure_not_settled(&market_state)?;
+
+    if !config.pairs.iter().any(|p| p.text == option) {
+        return Err(ContractError::Std(StdError::generic_err("Invalid option")));
+    }
+    if odds.is_zero() || liability_cap.is_zero() {
+        return Err(ContractError::InvalidHouseLine {});
+    }
+
+    // A lower cap stops new bets on the option, the bets already placed stand
+    HOUSE_LINES.save(
+        deps.storage,
+        &option,
+        &HouseLine {
+            odds,
+            liability_cap,
+        },
+    )?;
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_set_house_line")
+            .add_attribute("market_id", config.id)
+            .add_attribute("option", option)
+            .add_attribute("odds", odds.to_string())
+            .add_attribute("liability_cap", liability_cap.to_string()),
+    ))
+}
+
+pub fn withdraw_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let Some(positions) = house_positions(&config.pricing_mode) else {
+        return Err(ContractError::NoHouseLiquidity {});
+    };
+
+    // What the outstanding outcome tokens can still claim stays in the market
+    let rates: Vec<Decimal> = match &market_state.status {
+        MarketStatus::Resolved(_) => market_state.token_payouts(deps.storage, &config)?,
+        status if status.is_refundable() => config
+            .pairs
+            .iter()
+            .map(|option| {
+                Ok(REFUND_RATES
+                    .may_load(deps.storage, &option.text)?
+                    .unwrap_or_default())
+            })
+            .collect::<StdResult<_>>()?,
+        _ => return Err(ContractError::LiquidityLocked {}),
+    };
+    let mut liability = Uint128::zero();
+    for (option, rate) in config.pairs.iter().zip(rates) {
+        let quantity = positions
+            .may_load(deps.storage, &option.text)?
+            .unwrap_or_default();
+        liability += quantity.mul_ceil(rate);
+    }
+
+    let total_value = Uint128::from_str(&market_state.total_value.amount)?;
+    let amount = total_value.saturating_sub(liability);
+    market_state.total_value.amount = (total_value - amount).to_string();
+    save_market_state(deps.storage, &market_state)?;
+
+    let mut response = Response::new();
+    if !amount.is_zero() {
+        let withdraw_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: config.admin.to_string(),
+            amount: vec![Coin {
+                denom: config.buy_token.clone(),
+                amount: amount.to_string(),
+            }],
+        };
+        response = response.add_message(CosmosMsg::Any(withdraw_msg.to_any()));
+    }
+
+    Ok(response.add_event(
+        Event::new("cc_prediction_market_withdraw_liquidity")
+            .add_attribute("market_id", config.id)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("liability", liability.to_string()),
+    ))
+}
+
+/// Keeps the running share total of `option` in line with the tokens minted and burned for it
+fn update_share_total(
+    storage: &mut dyn Storage,
+    option: &str,
+    minted: Uint128,
+    burned: Uint128,
+) -> StdResult<Uint128> {
+    SHARE_TOTALS.update(storage, option, |total| -> StdResult<Uint128> {
+        Ok(total
+            .unwrap_or_default()
+            .checked_add(minted)?
+            .checked_sub(burned)?)
+    })
+}
+
+/// Moves `amount` shares of `option` out of a user's position, into an AMM pool
+fn debit_shares(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    option: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    SHARES.update(storage, (user, option), |existing| -> StdResult<Share> {
+        match existing {
+            Some(mut share) => {
+                if share.amount < amount {
+                    return Err(StdError::generic_err("Insufficient shares"));
+                }
+                share.amount -= amount;
+                Ok(share)
+            }
+            None => Err(StdError::generic_err("No shares found for user")),
+        }
+    })?;
+    Ok(())
+}
+
+/// Moves `amount` shares of `option` out of an AMM pool, into a user's position
+fn credit_shares(
+    storage: &mut dyn Storage,
+    config: &Config,
+    market_state: &mut MarketState,
+    user: &Addr,
+    option: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let is_new_bettor = !config
+        .pairs
+        .iter()
+        .any(|pair| SHARES.has(storage, (user, &pair.text)));
+    if is_new_bettor {
+        market_state.num_bettors += 1;
+    }
+
+    SHARES.update(storage, (user, option), |existing| -> StdResult<Share> {
+        match existing {
+            // Tokens received after a withdrawal, e.g. from a pool, can be withdrawn again
+            Some(share) => Ok(Share {
+                amount: share.amount + amount,
+                has_withdrawn: false,
+            }),
+            None => Ok(Share {
+                amount,
+                has_withdrawn: false,
+            }),
+        }
+    })?;
+    Ok(())
+}
+
+fn send_coin(env: &Env, to: &Addr, denom: &str, amount: Uint128) -> CosmosMsg {
+    let send_msg = MsgSend {
+        from_address: env.contract.address.to_string(),
+        to_address: to.to_string(),
+        amount: vec![Coin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        }],
+    };
+    CosmosMsg::Any(send_msg.to_any())
+}
+
+pub fn provide_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    option: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    ensure_market_active(&market_state, &config)?;
+
+    let market_option = config
+        .pairs
+        .iter()
+        .find(|p| p.text == option)
+        .ok_or_else(|| StdError::generic_err("Invalid option"))?;
+
+    if let Some(extra) = info.funds.iter().find(|coin| {
+        coin.denom != config.buy_token && coin.denom != market_option.associated_token_denom
+    }) {
+        return Err(ContractError::Payment(PaymentError::ExtraDenom(
+            extra.denom.clone(),
+        )));
+    }
+    let deposited = |denom: &str| -> Uint128 {
+        info.funds
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default()
+    };
+    let tokens = deposited(&market_option.associated_token_denom);
+    let buy_tokens = deposited(&config.buy_token);
+    if tokens.is_zero() || buy_tokens.is_zero() {
+        return Err(ContractError::InvalidPoolDeposit {});
+    }
+
+    debit_shares(deps.storage, &info.sender, &option, tokens)?;
+
+    // Extra tokens on either side of the pool ratio are left in the pool
+    let mut pool = AMM_POOLS.may_load(deps.storage, &option)?.unwrap_or_default();
+    let minted = if pool.lp_shares.is_zero() {
+        buy_tokens
+    } else {
+        pool.lp_shares
+            .multiply_ratio(tokens, pool.token_reserve)
+            .min(pool.lp_shares.multiply_ratio(buy_tokens, pool.buy_token_reserve))
+    };
+    if minted.is_zero() {
+        return Err(ContractError::InvalidPoolDeposit {});
+    }
+
+    pool.token_reserve += tokens;
+    pool.buy_token_reserve += buy_tokens;
+    pool.lp_shares += minted;
+    AMM_POOLS.save(deps.storage, &option, &pool)?;
+    let lp_shares = LP_SHARES.update(
+        deps.storage,
+        (&info.sender, &option),
+        |shares| -> StdResult<Uint128> { Ok(shares.unwrap_or_default() + minted) },
+    )?;
+
+    Ok(Response::new().add_event(
+        Event::new("cc_prediction_market_provide_liquidity")
+            .add_attribute("market_id", config.id)
+            .add_attribute("option", option)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("tokens", tokens.to_string())
+            .add_attribute("buy_tokens", buy_tokens.to_string())
+            .add_attribute("minted_lp_shares", minted.to_string())
+            .add_attribute("lp_shares", lp_shares.to_string()),
+    ))
+}
+
+pub fn remove_liquidity(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    option: String,
+    lp_shares: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    let market_option = config
+        .pairs
+        .iter()
+        .find(|p| p.text == option)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("Invalid option"))?;
+
+    let available = LP_SHARES
+        .may_load(deps.storage, (&info.sender, &option))?
+        .unwrap_or_default();
+    if lp_shares.is_zero() || lp_shares > available {
+        return Err(ContractError::InsufficientLpShares { available });
+    }
+
+    let mut pool = AMM_POOLS.load(deps.storage, &option)?;
+    let tokens = pool.token_reserve.multiply_ratio(lp_shares, pool.lp_shares);
+    let buy_tokens = pool.buy_token_reserve.multiply_ratio(lp_shares, pool.lp_shares);
+    pool.token_reserve -= tokens;
+    pool.buy_token_reserve -= buy_tokens;
+    pool.lp_shares -= lp_shares;
+    AMM_POOLS.save(deps.storage, &option, &pool)?;
+    LP_SHARES.save(deps.storage, (&info.sender, &option), &(available - lp_shares))?;
+
+    // The withdrawn tokens are shares of the provider again
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !tokens.is_zero() {
+        credit_shares(
+            deps.storage,
+            &config,
+            &mut market_state,
+            &info.sender,
+            &option,
+            tokens,
+        )?;
+        save_market_state(deps.storage, &market_state)?;
+        messages.push(send_coin(
+            &env,
+            &info.sender,
+            &market_option.associated_token_denom,
+            tokens,
+        ));
+    }
+    if !buy_tokens.is_zero() {
+        messages.push(send_coin(&env, &info.sender, &config.buy_token, buy_tokens));
+    }
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_remove_liquidity")
+                .add_attribute("market_id", config.id)
+                .add_attribute("option", option)
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("lp_shares", lp_shares.to_string())
+                .add_attribute("tokens", tokens.to_string())
+                .add_attribute("buy_tokens", buy_tokens.to_string()),
+        )
+        .add_messages(messages))
+}
+
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    option: String,
+    min_amount_out: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    ensure_market_active(&market_state, &config)?;
+    ensure_barrier_not_hit(deps.as_ref())?;
+
+    let market_option = config
+        .pairs
+        .iter()
+        .find(|p| p.text == option)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("Invalid option"))?;
+
+    // Paying buy_token buys outcome tokens, paying outcome tokens sells them
+    let amount_in = one_coin(&info)?;
+    let buying = if amount_in.denom == config.buy_token {
+        true
+    } else if amount_in.denom == market_option.associated_token_denom {
+        false
+    } else {
+        return Err(ContractError::Payment(PaymentError::ExtraDenom(
+            amount_in.denom,
+        )));
+    };
+
+    let mut pool = AMM_POOLS.may_load(deps.storage, &option)?.unwrap_or_default();
+    if pool.lp_shares.is_zero() {
+        return Err(ContractError::EmptyPool { option });
+    }
+
+    let amount_out = pool.amount_out(amount_in.amount, buying, config.commission_rate);
+    let min_amount_out = min_amount_out.unwrap_or(Uint128::one());
+    if amount_out < min_amount_out {
+        return Err(ContractError::SlippageExceeded {
+            min_amount_out,
+            amount_out,
+        });
+    }
+
+    let out_msg = if buying {
+        pool.buy_token_reserve += amount_in.amount;
+        pool.token_reserve -= amount_out;
+        market_state.volume += amount_in.amount;
+        credit_shares(
+            deps.storage,
+            &config,
+            &mut market_state,
+            &info.sender,
+            &option,
+            amount_out,
+        )?;
+        send_coin(
+            &env,
+            &info.sender,
+            &market_option.associated_token_denom,
+            amount_out,
+        )
+    } else {
+        debit_shares(deps.storage, &info.sender, &option, amount_in.amount)?;
+        pool.token_reserve += amount_in.amount;
+        pool.buy_token_reserve -= amount_out;
+        market_state.volume += amount_out;
+        send_coin(&env, &info.sender, &config.buy_token, amount_out)
+    };
+    AMM_POOLS.save(deps.storage, &option, &pool)?;
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_swap")
+                .add_attribute("market_id", config.id)
+                .add_attribute("option", option)
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("amount_in", amount_in.to_string())
+                .add_attribute("amount_out", amount_out.to_string())
+                .add_attribute(
+                    "price",
+                    pool.price().map(|p| p.to_string()).unwrap_or_default(),
+                ),
+        )
+        .add_message(out_msg))
+}
+
+/// Removes an order from the book and the open orders of its owner
+fn close_order(storage: &mut dyn Storage, order: &Order) {
+    ORDERS.remove(storage, order.id);
+    match order.side {
+        OrderSide::Bid => BIDS.remove(storage, order.book_key()),
+        OrderSide::Ask => ASKS.remove(storage, order.book_key()),
+    }
+    USER_ORDERS.remove(storage, (&order.owner, order.id));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    option: String,
+    side: OrderSide,
+    price: Decimal,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    // Orders only match while the market is active
+    ensure_market_active(&market_state, &config)?;
+    ensure_barrier_not_hit(deps.as_ref())?;
+
+    let market_option = config
+        .pairs
+        .iter()
+        .find(|p| p.text == option)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("Invalid option"))?;
+
+    if price.is_zero() || amount.is_zero() {
+        return Err(ContractError::InvalidOrder {});
+    }
+
+    // Bids escrow buy_token, asks escrow the outcome tokens and the shares behind them
+    let (denom, required) = match side {
+        OrderSide::Bid => (&config.buy_token, amount.mul_ceil(price)),
+        OrderSide::Ask => (&market_option.associated_token_denom, amount),
+    };
+    let sent = must_pay(&info, denom)?;
+    if sent != required {
+        return Err(ContractError::InvalidOrderFunds { required, sent });
+    }
+    if side == OrderSide::Ask {
+        debit_shares(deps.storage, &info.sender, &option, amount)?;
+    }
+
+    let id = NEXT_ORDER_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_ORDER_ID.save(deps.storage, &(id + 1))?;
+    let mut order = Order {
+        id,
+        owner: info.sender.clone(),
+        option: option.clone(),
+        escrow: match side {
+            OrderSide::Bid => sent,
+            OrderSide::Ask => Uint128::zero(),
+        },
+        side,
+        price,
+        amount,
+        created_at: env.block.time,
+    };
+
+    // Best opposite orders first, each one fills at its own price
+    let opposite = match order.side {
+        OrderSide::Bid => &ASKS,
+        OrderSide::Ask => &BIDS,
+    };
+    let maker_ids: Vec<u64> = opposite
+        .sub_prefix(&option)
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .take(MAX_ORDER_FILLS)
+        .map(|key| key.map(|(_, id)| id))
+        .collect::<StdResult<_>>()?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut filled = Uint128::zero();
+    for maker_id in maker_ids {
+        if order.is_filled() {
+            break;
+        }
+        let mut maker = ORDERS.load(deps.storage, maker_id)?;
+        let crosses = match order.side {
+            OrderSide::Bid => maker.price <= order.price,
+            OrderSide::Ask => maker.price >= order.price,
+        };
+        if !crosses {
+            break;
+        }
+
+        // Fills are rounded up so no tokens change hands for free, within what the bid escrowed
+        let fill = order.amount.min(maker.amount);
+        let bid_escrow = match order.side {
+            OrderSide::Bid => order.escrow,
+            OrderSide::Ask => maker.escrow,
+        };
+        let cost = fill.mul_ceil(maker.price).min(bid_escrow);
+        if cost.is_zero() {
+            // A maker bid that spent its escrow can't buy anything, take it off the book
+            close_order(deps.storage, &maker);
+            continue;
+        }
+        order.amount -= fill;
+        maker.amount -= fill;
+        filled += fill;
+        market_state.volume += cost;
+
+        let (buyer, seller) = match order.side {
+            OrderSide::Bid => {
+                order.escrow -= cost;
+                (order.owner.clone(), maker.owner.clone())
+            }
+            OrderSide::Ask => {
+                maker.escrow -= cost;
+                (maker.owner.clone(), order.owner.clone())
+            }
+        };
+        credit_shares(
+            deps.storage,
+            &config,
+            &mut market_state,
+            &buyer,
+            &option,
+            fill,
+        )?;
+        messages.push(send_coin(
+            &env,
+            &buyer,
+            &market_option.associated_token_denom,
+            fill,
+        ));
+        messages.push(send_coin(&env, &seller, &config.buy_token, cost));
+
+        if maker.is_filled() {
+            close_order(deps.storage, &maker);
+            if !maker.escrow.is_zero() {
+                messages.push(send_coin(&env, &maker.owner, &config.buy_token, maker.escrow));
+            }
+        } else {
+            ORDERS.save(deps.storage, maker.id, &maker)?;
+        }
+    }
+
+    // A bid that filled below its price gets the difference back, the rest of the order stays open
+    if order.is_filled() {
+        if !order.escrow.is_zero() {
+            messages.push(send_coin(&env, &order.owner, &config.buy_token, order.escrow));
+        }
+    } else {
+        ORDERS.save(deps.storage, order.id, &order)?;
+        match order.side {
+            OrderSide::Bid => BIDS.save(deps.storage, order.book_key(), &Empty {})?,
+            OrderSide::Ask => ASKS.save(deps.storage, order.book_key(), &Empty {})?,
+        }
+        USER_ORDERS.save(deps.storage, (&order.owner, order.id), &Empty {})?;
+    }
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_place_order")
+                .add_attribute("market_id", config.id)
+                .add_attribute("order_id", order.id.to_string())
+                .add_attribute("option", option)
+                .add_attribute("side", format!("{:?}", order.side))
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("price", price.to_string())
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("filled", filled.to_string())
+                .add_attribute("open_amount", order.amount.to_string()),
+        )
+        .add_messages(messages))
+}
+
+pub fn cancel_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+
+    let order = ORDERS
+        .may_load(deps.storage, order_id)?
+        .ok_or(ContractError::OrderNotFound { order_id })?;
+    if order.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    close_order(deps.storage, &order);
+
+    // Unfilled asks are shares of the owner again
+    let refund_msg = match order.side {
+        OrderSide::Bid => send_coin(&env, &order.owner, &config.buy_token, order.escrow),
+        OrderSide::Ask => {
+            credit_shares(
+                deps.storage,
+                &config,
+                &mut market_state,
+                &order.owner,
+                &order.option,
+                order.amount,
+            )?;
+            MARKET_STATE.save(deps.storage, &market_state)?;
+            let market_option = config
+                .pairs
+                .iter()
+                .find(|p| p.text == order.option)
+                .ok_or_else(|| StdError::generic_err("Invalid option"))?;
+            send_coin(
+                &env,
+                &order.owner,
+                &market_option.associated_token_denom,
+                order.amount,
+            )
+        }
+    };
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_cancel_order")
+                .add_attribute("market_id", config.id)
+                .add_attribute("order_id", order_id.to_string())
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("amount", order.amount.to_string())
+                .add_attribute("escrow", order.escrow.to_string()),
+        )
+        .add_message(refund_msg))
+}
+
+pub fn cancel_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+    market_state.refresh_status(&config, env.block.time);
+
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Once trading has closed the outcome may be known, such markets can only be voided
+    ensure_not_settled(&market_state)?;
+    if market_state.status == MarketStatus::Closed {
+        return Err(ContractError::MarketClosed {
+            end_time: config.end_time,
+        });
+    }
+
+    save_pro_rata_refund_rates(deps.storage, &config, &market_state)?;
+
+    market_state.status = MarketStatus::Cancelled;
+
+    // No resolution will happen, the held back commissions go to the admin
+    let bounty = market_state.bounty_reserve;
+    market_state.bounty_reserve = Uint128::zero();
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    let mut response = Response::new();
+    if !bounty.is_zero() {
+        let bounty_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: config.admin.to_string(),
+            amount: vec![Coin {
+                denom: config.buy_token.clone(),
+                amount: bounty.to_string(),
+            }],
+        };
+        response = response.add_message(CosmosMsg::Any(bounty_msg.to_any()));
+    }
+
+    Ok(response.add_event(
+        Event::new("cc_prediction_market_cancel")
+            .add_attribute("market_id", config.id)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("total_value", market_state.total_value.amount)
+            .add_attribute("bounty", bounty.to_string()),
+    ))
+}
+
+/// Each option's stake is split pro-rata between the shares still held for it.
+/// Sell taxes left in the pot are therefore returned to the remaining holders.
+fn save_pro_rata_refund_rates(
+    storage: &mut dyn Storage,
+    config: &Config,
+    market_state: &MarketState,
+) -> StdResult<()> {
+    // Outstanding shares of each option, tokens in its AMM pool and open asks included
+    let total_shares: Vec<Uint128> = config
+        .pairs
+        .iter()
+        .map(|option| {
+            Ok(SHARE_TOTALS
+                .may_load(storage, &option.text)?
+                .unwrap_or_default())
+        })
+        .collect::<StdResult<_>>()?;
+
+    let total_stakes = market_state.total_stakes(storage, config)?;
+    for (index, total_stake) in total_stakes.into_iter().enumerate() {
+        let rate = if total_shares[index].is_zero() {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(total_stake, total_shares[index])
+        };
+        REFUND_RATES.save(storage, &config.pairs[index].text, &rate)?;
+    }
+
+    Ok(())
+}
+
+pub fn void_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    _market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+
+    // A dispute nobody ruled on before the deadline is voided too,
+    // whether there is no arbitrator or the arbitrator never ruled
+    let stuck_dispute = matches!(market_state.status, MarketStatus::Disputed(_));
+    if !stuck_dispute {
+        ensure_not_settled(&market_state)?;
+    }
+
+    if env.block.time < config.resolution_deadline {
+        return Err(ContractError::ResolutionDeadlineNotReached {
+            deadline: config.resolution_deadline,
+        });
+    }
+
+    // Every position is refunded at its cost basis: one buy_token per outcome token,
+    // or the option's average purchase price with LMSR and fixed odds
+    match config.pricing_mode {
+        PricingMode::Parimutuel => {
+            for option in &config.pairs {
+                REFUND_RATES.save(deps.storage, &option.text, &Decimal::one())?;
+            }
+        }
+        PricingMode::Lmsr { .. } | PricingMode::FixedOdds => {
+            save_pro_rata_refund_rates(deps.storage, &config, &market_state)?
+        }
+    }
+
+    market_state.status = MarketStatus::Void;
+
+    // Voiding settles the market, the caller collects the resolution bounty
+    let bounty = market_state.bounty_reserve;
+    market_state.bounty_reserve = Uint128::zero();
+    MARKET_STATE.save(deps.storage, &market_state)?;
+
+    let mut response = Response::new();
+    if !bounty.is_zero() {
+        let bounty_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.buy_token.clone(),
+                amount: bounty.to_string(),
+            }],
+        };
+        response = response.add_message(CosmosMsg::Any(bounty_msg.to_any()));
+    }
+
+    // Nobody ruled against the challenger, the bond is returned
+    if let Some(dispute) = DISPUTE.may_load(deps.storage)? {
+        response = response.add_message(send_coin(
+            &env,
+            &dispute.challenger,
+            &config.buy_token,
+            dispute.bond,
+        ));
+    }
+
+    Ok(response.add_event(
+        Event::new("cc_prediction_market_void")
+            .add_attribute("market_id", config.id)
+            .add_attribute("user", info.sender.to_string())
+            .add_attribute("resolution_deadline", config.resolution_deadline.to_string())
+            .add_attribute("total_value", market_state.total_value.amount)
+            .add_attribute("bounty", bounty.to_string()),
+    ))
+}
+
+pub fn claim_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut market_state = MARKET_STATE.load(deps.storage)?;
+
+    if !market_state.status.is_refundable() {
+        return Err(ContractError::RefundsNotAvailable {});
+    }
+
+    if info.funds.is_empty() {
+        return Err(ContractError::Payment(cw_utils::PaymentError::NoFunds {}));
+    }
+
+    let mut refund_amount = Uint128::zero();
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    // The user returns outcome tokens of any option, each one is refunded at its option rate
+    for returned in &info.funds {
+        let market_option = config
+            .pairs
+            .iter()
+            .find(|p| p.associated_token_denom == returned.denom)
+            .ok_or_else(|| ContractError::InvalidRefundToken {
+                denom: returned.denom.clone(),
+            })?;
+
+        SHARES.update(
+            deps.storage,
+            (&info.sender, &market_option.text),
+            |existing| -> StdResult<Share> {
+                match existing {
+                    Some(mut share) => {
+                        if share.amount < returned.amount {
+                            return Err(StdError::generic_err("Insufficient shares to refund"));
+                        }
+                        share.amount -= returned.amount;
+                        Ok(share)
+                    }
+                    None => Err(StdError::generic_err("No shares found for user")),
+                }
+            },
+        )?;
+        update_share_total(
+            deps.storage,
+            &market_option.text,
+            Uint128::zero(),
+            returned.amount,
+        )?;
+
+        let rate = REFUND_RATES
+            .may_load(deps.storage, &market_option.text)?
+            .unwrap_or_default();
+        refund_amount += returned.amount.mul_floor(rate);
+
+        if let Some(positions) = house_positions(&config.pricing_mode) {
+            positions.update(
+                deps.storage,
+                &market_option.text,
+                |quantity| -> StdResult<Uint128> {
+                    Ok(quantity.unwrap_or_default().saturating_sub(returned.amount))
+                },
+            )?;
+        }
+
+        let burn_msg = MsgBurn {
+            sender: env.contract.address.to_string(),
+            coin: Some(Coin {
+                denom: returned.denom.clone(),
+                amount: returned.amount.to_string(),
+            }),
+        };
+        messages.push(CosmosMsg::Any(burn_msg.to_any()));
+    }
+
+    // LMSR and fixed-odds refunds come out of the house funds
+    if config.pricing_mode != PricingMode::Parimutuel {
+        market_state.total_value.amount = Uint128::from_str(&market_state.total_value.amount)?
+            .saturating_sub(refund_amount)
+            .to_string();
+        MARKET_STATE.save(deps.storage, &market_state)?;
+    }
+
+    if !refund_amount.is_zero() {
+        let refund_msg = MsgSend {
+            from_address: env.contract.address.to_string(),
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.buy_token.clone(),
+                amount: refund_amount.to_string(),
+            }],
+        };
+        messages.push(CosmosMsg::Any(refund_msg.to_any()));
+    }
+
+    Ok(Response::new()
+        .add_event(
+            Event::new("cc_prediction_market_claim_refund")
+                .add_attribute("market_id", market_id)
+                .add_attribute("user", info.sender.to_string())
+                .add_attribute("refund_amount", refund_amount.to_string()),
+        )
+        .add_messages(messages))
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetMarket { id } => to_json_binary(&query::query_market(deps, id)?),
+        QueryMsg::GetMarket { id } => to_json_binary(&query::query_market(deps, _env, id)?),
         QueryMsg::GetShares { market_id, user } => {
             to_json_binary(&query::query_shares(deps, market_id, user)?)
         }
@@ -644,7 +3674,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_json_binary(&query::query_market_stats(deps, market_id)?)
         }
         QueryMsg::GetUserWinnings { market_id, user } => {
-            to_json_binary(&query::query_user_winnings(deps, market_id, user)?)
+            to_json_binary(&query::query_user_winnings(deps, _env, market_id, user)?)
         }
         QueryMsg::GetUserPotentialWinnings { market_id, user } => to_json_binary(
             &query::query_user_potential_winnings(deps, market_id, user)?,
@@ -666,6 +3696,37 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::SimulateSell { option, amount } => {
             to_json_binary(&query::query_simulate_sell(deps, _env, option, amount)?)
         }
+        QueryMsg::GetResolution { market_id } => {
+            to_json_binary(&query::query_resolution(deps, market_id)?)
+        }
+        QueryMsg::GetTwapObservations { market_id } => {
+            to_json_binary(&query::query_twap_observations(deps, market_id)?)
+        }
+        QueryMsg::GetPrices { market_id } => {
+            to_json_binary(&query::query_prices(deps, market_id)?)
+        }
+        QueryMsg::GetPool { market_id, option } => {
+            to_json_binary(&query::query_pool(deps, market_id, option)?)
+        }
+        QueryMsg::GetLpPosition {
+            market_id,
+            option,
+            user,
+        } => to_json_binary(&query::query_lp_position(deps, market_id, option, user)?),
+        QueryMsg::GetOrderBook {
+            market_id,
+            option,
+            limit,
+        } => to_json_binary(&query::query_order_book(deps, market_id, option, limit)?),
+        QueryMsg::GetOpenOrders { market_id, user } => {
+            to_json_binary(&query::query_open_orders(deps, market_id, user)?)
+        }
+        QueryMsg::GetHouseLines { market_id } => {
+            to_json_binary(&query::query_house_lines(deps, market_id)?)
+        }
+        QueryMsg::GetBets { market_id, user } => {
+            to_json_binary(&query::query_bets(deps, market_id, user)?)
+        }
     }
 }
 pub mod query {
@@ -675,9 +3736,12 @@ pub mod query {
     use cosmwasm_std::Addr;
 
     use crate::msg::{
-        AllSharesResponse, MarketResponse, MarketStatsResponse, OddsResponse, ShareResponse,
-        SimulateSellResponse, TaxRateResponse, TotalSharesPerOptionResponse, TotalValueResponse,
-        UserPotentialWinningsResponse, UserWinningsResponse,
+        AllSharesResponse, BetsResponse, HouseLinesResponse, LpPositionResponse, MarketResponse,
+        MarketStatsResponse, OddsResponse, OpenOrdersResponse, OptionHouseLine, OptionPrice,
+        OrderBookResponse, OutcomeVote, PoolResponse, PriceLevel, PricesResponse,
+        ResolutionResponse, ShareResponse, SimulateSellResponse, TaxRateResponse,
+        TotalSharesPerOptionResponse, TotalValueResponse, TwapObservation,
+        TwapObservationsResponse, UserPotentialWinningsResponse, UserWinningsResponse,
     };
 
     use super::*;
@@ -687,7 +3751,7 @@ pub mod query {
         let config = CONFIG.load(deps.storage)?;
 
         Ok(OddsResponse {
-            odds: market_state.create_type_safe_odds(&config),
+            odds: market_state.create_type_safe_odds(deps.storage, &config)?,
         })
     }
 
@@ -705,26 +3769,21 @@ pub mod query {
     ) -> StdResult<TotalSharesPerOptionResponse> {
         let market_state = MARKET_STATE.load(deps.storage)?;
         let config = CONFIG.load(deps.storage)?;
-        let (total_a, total_b) = market_state.total_stakes(&config);
+        let totals = market_state.total_stakes(deps.storage, &config)?;
 
-        let options = vec![
-            crate::msg::OptionShares {
-                option: config.pairs[0].text.clone(),
-                token_denom: config.pairs[0].associated_token_denom.clone(),
-                total_staked: Coin {
-                    denom: config.buy_token.clone(),
-                    amount: total_a.to_string(),
-                },
-            },
-            crate::msg::OptionShares {
-                option: config.pairs[1].text.clone(),
-                token_denom: config.pairs[1].associated_token_denom.clone(),
+        let options = config
+            .pairs
+            .iter()
+            .zip(totals.iter())
+            .map(|(pair, total)| crate::msg::OptionShares {
+                option: pair.text.clone(),
+                token_denom: pair.associated_token_denom.clone(),
                 total_staked: Coin {
                     denom: config.buy_token.clone(),
-                    amount: total_b.to_string(),
+                    amount: total.to_string(),
                 },
-            },
-        ];
+            })
+            .collect();
 
         Ok(TotalSharesPerOptionResponse {
             options,
@@ -732,12 +3791,12 @@ pub mod query {
             option_a: config.pairs[0].clone(),
             amount_a: Coin {
                 denom: config.buy_token.clone(),
-                amount: total_a.to_string(),
+                amount: totals[0].to_string(),
             },
             option_b: config.pairs[1].clone(),
             amount_b: Coin {
                 denom: config.buy_token.clone(),
-                amount: total_b.to_string(),
+                amount: totals[1].to_string(),
             },
         })
     }
@@ -765,26 +3824,24 @@ pub mod query {
         Ok(AllSharesResponse { shares })
     }
 
-    pub fn query_market(deps: Deps, _id: String) -> StdResult<MarketResponse> {
+    pub fn query_market(deps: Deps, env: Env, _id: String) -> StdResult<MarketResponse> {
         let config = CONFIG.load(deps.storage)?;
-        let market_state = MARKET_STATE.load(deps.storage)?;
-        let (total_a, total_b) = market_state.total_stakes(&config);
-        let (odds_a, odds_b) = market_state.calculate_odds(&config);
-
-        let options_with_odds = vec![
-            crate::msg::OptionWithOdds {
-                option: config.pairs[0].text.clone(),
-                odds: odds_a.to_string(),
-                token_denom: config.pairs[0].associated_token_denom.clone(),
-                total_staked: total_a.to_string(),
-            },
-            crate::msg::OptionWithOdds {
-                option: config.pairs[1].text.clone(),
-                odds: odds_b.to_string(),
-                token_denom: config.pairs[1].associated_token_denom.clone(),
-                total_staked: total_b.to_string(),
-            },
-        ];
+        let mut market_state = MARKET_STATE.load(deps.storage)?;
+        market_state.refresh_status(&config, env.block.time);
+        let totals = market_state.total_stakes(deps.storage, &config)?;
+        let odds = market_state.calculate_odds(deps.storage, &config)?;
+
+        let options_with_odds = config
+            .pairs
+            .iter()
+            .zip(totals.iter().zip(odds.iter()))
+            .map(|(pair, (total, odds))| crate::msg::OptionWithOdds {
+                option: pair.text.clone(),
+                odds: odds.to_string(),
+                token_denom: pair.associated_token_denom.clone(),
+                total_staked: total.to_string(),
+            })
+            .collect();
 
         Ok(MarketResponse {
             id: config.id,
@@ -792,13 +3849,14 @@ pub mod query {
             status: market_state.status,
             total_value: market_state.total_value,
             num_bettors: market_state.num_bettors,
+            // Keep legacy fields for backward compatibility
             token_a: Coin {
                 denom: config.pairs[0].associated_token_denom.clone(),
-                amount: total_a.to_string(),
+                amount: totals[0].to_string(),
             },
             token_b: Coin {
                 denom: config.pairs[1].associated_token_denom.clone(),
-                amount: total_b.to_string(),
+                amount: totals[1].to_string(),
             },
             buy_token: config.buy_token,
             banner_url: config.banner_url,
@@ -842,23 +3900,14 @@ pub mod query {
         let market_state = MARKET_STATE.load(deps.storage)?;
         let config = CONFIG.load(deps.storage)?;
 
-        let (odds_a, odds_b) = market_state.calculate_odds(&config);
-
-        let options_odds = vec![
-            crate::msg::OptionOdds {
-                option: config.pairs[0].text.clone(),
-                odds: odds_a,
-            },
-            crate::msg::OptionOdds {
-                option: config.pairs[1].text.clone(),
-                odds: odds_b,
-            },
-        ];
+        let options_odds = market_state.create_type_safe_odds(deps.storage, &config)?;
+        let (odds_a, odds_b) = (options_odds[0].odds, options_odds[1].odds);
 
         Ok(MarketStatsResponse {
             total_value: market_state.total_value,
             num_bettors: market_state.num_bettors,
             options_odds,
+            complete_sets: COMPLETE_SETS.may_load(deps.storage)?.unwrap_or_default(),
             // Keep legacy fields for backward compatibility
             odds_a,
             odds_b,
@@ -873,35 +3922,35 @@ pub mod query {
         let market_state = MARKET_STATE.load(deps.storage)?;
         let config = CONFIG.load(deps.storage)?;
 
-        let (winnings_a, winnings_b) =
-            market_state.calculate_potential_winnings(deps.storage, &user, &config)?;
+        let winnings = market_state.calculate_potential_winnings(deps.storage, &user, &config)?;
 
-        let options = vec![
-            crate::msg::OptionPotentialWinning {
-                option: config.pairs[0].text.clone(),
-                potential_winnings: winnings_a.clone(),
-            },
-            crate::msg::OptionPotentialWinning {
-                option: config.pairs[1].text.clone(),
-                potential_winnings: winnings_b.clone(),
-            },
-        ];
+        let options = config
+            .pairs
+            .iter()
+            .zip(winnings.iter())
+            .map(|(pair, potential_winnings)| crate::msg::OptionPotentialWinning {
+                option: pair.text.clone(),
+                potential_winnings: potential_winnings.clone(),
+            })
+            .collect();
 
         Ok(UserPotentialWinningsResponse {
             options,
             // Keep legacy fields for backward compatibility
-            potential_win_a: winnings_a,
-            potential_win_b: winnings_b,
+            potential_win_a: winnings[0].clone(),
+            potential_win_b: winnings[1].clone(),
         })
     }
 
     pub fn query_user_winnings(
         deps: Deps,
+        env: Env,
         _market_id: String,
         user: Addr,
     ) -> StdResult<UserWinningsResponse> {
-        let market_state = MARKET_STATE.load(deps.storage)?;
+        let mut market_state = MARKET_STATE.load(deps.storage)?;
         let config = CONFIG.load(deps.storage)?;
+        market_state.refresh_status(&config, env.block.time);
         let winnings = market_state.calculate_winnings(deps.storage, &user, &config)?;
         Ok(UserWinningsResponse { winnings })
     }
@@ -914,13 +3963,218 @@ pub mod query {
         request.query(&deps.querier)
     }
 
+    pub fn query_resolution(deps: Deps, _market_id: String) -> StdResult<ResolutionResponse> {
+        Ok(ResolutionResponse {
+            resolution: RESOLUTION.may_load(deps.storage)?,
+            dispute: DISPUTE.may_load(deps.storage)?,
+            strike: STRIKE_PRICE.may_load(deps.storage)?,
+            second_strike: SECOND_STRIKE_PRICE.may_load(deps.storage)?,
+            barrier_hit: BARRIER_HIT.may_load(deps.storage)?,
+            votes: OUTCOME_VOTES
+                .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                .map(|vote| {
+                    let (resolver, option) = vote?;
+                    Ok(OutcomeVote { resolver, option })
+                })
+                .collect::<StdResult<Vec<_>>>()?,
+        })
+    }
+
+    pub fn query_twap_observations(
+        deps: Deps,
+        _market_id: String,
+    ) -> StdResult<TwapObservationsResponse> {
+        let config = CONFIG.load(deps.storage)?;
+
+        let observations = TWAP_OBSERVATIONS
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .map(|item| {
+                let ((asset, nanos), price) = item?;
+                Ok(TwapObservation {
+                    asset,
+                    price,
+                    timestamp: Timestamp::from_nanos(nanos),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let twap = |asset: &str| -> StdResult<Option<Decimal>> {
+            Ok(twap_price(deps.storage, &config, asset)?.map(|twap| twap.price))
+        };
+
+        Ok(TwapObservationsResponse {
+            window_start: config
+                .twap_window
+                .map(|twap_window| config.end_time.minus_seconds(twap_window)),
+            twap: twap(&config.asset_to_track)?,
+            second_twap: match &config.second_asset_to_track {
+                Some(second_asset) => twap(second_asset)?,
+                None => None,
+            },
+            observations,
+        })
+    }
+
+    pub fn query_prices(deps: Deps, _market_id: String) -> StdResult<PricesResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let market_state = MARKET_STATE.load(deps.storage)?;
+
+        let prices = market_state.calculate_prices(deps.storage, &config)?;
+
+        Ok(PricesResponse {
+            prices: config
+                .pairs
+                .iter()
+                .zip(prices)
+                .map(|(option, price)| OptionPrice {
+                    option: option.text.clone(),
+                    price,
+                })
+                .collect(),
+        })
+    }
+
+    pub fn query_pool(deps: Deps, _market_id: String, option: String) -> StdResult<PoolResponse> {
+        let pool = AMM_POOLS.may_load(deps.storage, &option)?.unwrap_or_default();
+
+        Ok(PoolResponse {
+            price: pool.price(),
+            option,
+            token_reserve: pool.token_reserve,
+            buy_token_reserve: pool.buy_token_reserve,
+            lp_shares: pool.lp_shares,
+        })
+    }
+
+    pub fn query_lp_position(
+        deps: Deps,
+        _market_id: String,
+        option: String,
+        user: Addr,
+    ) -> StdResult<LpPositionResponse> {
+        let pool = AMM_POOLS.may_load(deps.storage, &option)?.unwrap_or_default();
+        let lp_shares = LP_SHARES
+            .may_load(deps.storage, (&user, &option))?
+            .unwrap_or_default();
+        if lp_shares.is_zero() {
+            return Ok(LpPositionResponse {
+                lp_shares,
+                token_amount: Uint128::zero(),
+                buy_token_amount: Uint128::zero(),
+            });
+        }
+
+        Ok(LpPositionResponse {
+            lp_shares,
+            token_amount: pool.token_reserve.multiply_ratio(lp_shares, pool.lp_shares),
+            buy_token_amount: pool.buy_token_reserve.multiply_ratio(lp_shares, pool.lp_shares),
+        })
+    }
+
+    /// Open amount per price level of one side of the book, up to `limit` levels
+    fn book_depth(
+        deps: Deps,
+        book: &Map<(&str, u128, u64), Empty>,
+        option: &str,
+        limit: usize,
+    ) -> StdResult<Vec<PriceLevel>> {
+        let mut levels: Vec<PriceLevel> = vec![];
+        for key in book
+            .sub_prefix(option)
+            .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        {
+            let (_, order_id) = key?;
+            let order = ORDERS.load(deps.storage, order_id)?;
+            if let Some(level) = levels.last_mut().filter(|level| level.price == order.price) {
+                level.amount += order.amount;
+            } else if levels.len() == limit {
+                break;
+            } else {
+                levels.push(PriceLevel {
+                    price: order.price,
+                    amount: order.amount,
+                });
+            }
+        }
+        Ok(levels)
+    }
+
+    pub fn query_order_book(
+        deps: Deps,
+        _market_id: String,
+        option: String,
+        limit: Option<u32>,
+    ) -> StdResult<OrderBookResponse> {
+        let limit = limit
+            .unwrap_or(DEFAULT_BOOK_DEPTH)
+            .min(MAX_BOOK_DEPTH) as usize;
+
+        Ok(OrderBookResponse {
+            bids: book_depth(deps, &BIDS, &option, limit)?,
+            asks: book_depth(deps, &ASKS, &option, limit)?,
+            option,
+        })
+    }
+
+    pub fn query_open_orders(
+        deps: Deps,
+        _market_id: String,
+        user: Addr,
+    ) -> StdResult<OpenOrdersResponse> {
+        let orders = USER_ORDERS
+            .prefix(&user)
+            .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .map(|order_id| ORDERS.load(deps.storage, order_id?))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OpenOrdersResponse { orders })
+    }
+
+    pub fn query_house_lines(deps: Deps, _market_id: String) -> StdResult<HouseLinesResponse> {
+        let config = CONFIG.load(deps.storage)?;
+
+        let lines = config
+            .pairs
+            .iter()
+            .map(|option| {
+                let line = HOUSE_LINES.may_load(deps.storage, &option.text)?;
+                Ok(OptionHouseLine {
+                    option: option.text.clone(),
+                    odds: line.as_ref().map(|line| line.odds),
+                    liability_cap: line.map(|line| line.liability_cap).unwrap_or_default(),
+                    liability: LIABILITIES
+                        .may_load(deps.storage, &option.text)?
+                        .unwrap_or_default(),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(HouseLinesResponse {
+            bankroll: BANKROLL.may_load(deps.storage)?.unwrap_or_default(),
+            lines,
+        })
+    }
+
+    pub fn query_bets(deps: Deps, _market_id: String, user: Addr) -> StdResult<BetsResponse> {
+        let bets = BETS
+            .prefix(&user)
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .map(|item| item.map(|(_, bet)| bet))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BetsResponse { bets })
+    }
+
     pub fn query_tax_rate(deps: Deps, env: Env) -> StdResult<TaxRateResponse> {
         let config = CONFIG.load(deps.storage)?;
         let market_state = MARKET_STATE.load(deps.storage)?;
 
         let tax_rate = market_state.calculate_time_based_tax(&config, env.block.time);
 
-        Ok(TaxRateResponse { tax_rate })
+        Ok(TaxRateResponse {
+            tax_rate,
+            tax_config: config.tax_config,
+        })
     }
 
     pub fn query_simulate_sell(
@@ -933,27 +4187,45 @@ pub mod query {
         let market_state = MARKET_STATE.load(deps.storage)?;
 
         // Validate option exists
-        let _market_option = config
+        let index = config
             .pairs
             .iter()
-            .find(|p| p.text == option)
+            .position(|p| p.text == option)
             .ok_or_else(|| StdError::generic_err("Invalid option"))?;
 
         // Parse amount
         let amount_sent = Uint128::from_str(&amount)
             .map_err(|_| StdError::generic_err("Invalid amount format"))?;
 
+        // LMSR tokens are worth what the cost function pays for them
+        let sell_value = match &config.pricing_mode {
+            PricingMode::Parimutuel => amount_sent,
+            PricingMode::Lmsr { liquidity } => market_state.lmsr_sell_value(
+                deps.storage,
+                &config,
+                *liquidity,
+                index,
+                amount_sent,
+            )?,
+            PricingMode::FixedOdds => {
+                return Err(StdError::generic_err(
+                    "Fixed-odds bets can't be sold back to the house",
+                ))
+            }
+        };
+
         // Calculate tax
         let tax_rate = market_state.calculate_time_based_tax(&config, env.block.time);
         let amount_after_tax =
-            market_state.calculate_sell_amount_with_tax(&config, amount_sent, env.block.time);
-        let tax_amount = amount_sent - amount_after_tax;
+            market_state.calculate_sell_amount_with_tax(&config, sell_value, env.block.time);
+        let tax_amount = sell_value - amount_after_tax;
 
         Ok(SimulateSellResponse {
             amount_sent: amount_sent.to_string(),
             tax_rate,
             tax_amount: tax_amount.to_string(),
             amount_after_tax: amount_after_tax.to_string(),
+            tax_config: config.tax_config,
         })
     }
 }
@@ -965,6 +4237,98 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     if ver.contract != CONTRACT_NAME {
         return Err(StdError::generic_err("Can only upgrade from same contract type").into());
     }
+
+    // Markets instantiated by older versions still hold the legacy layout
+    if CONFIG.load(deps.storage).is_err() {
+        migrate_legacy_config(deps.storage)?;
+    }
+    if MARKET_STATE.load(deps.storage).is_err() {
+        migrate_legacy_market_state(deps.storage)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
+
+/// Rewrites the legacy config with the defaults of the settings added since
+fn migrate_legacy_config(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy = LEGACY_CONFIG.load(storage)?;
+
+    // Legacy UpDown markets compared the settlement price with target_price
+    if legacy.market_type == MarketType::UpDown {
+        STRIKE_PRICE.save(
+            storage,
+            &PriceSnapshot {
+                price: legacy.target_price,
+                timestamp: legacy.start_time,
+            },
+        )?;
+    }
+
+    let config = Config {
+        id: legacy.id,
+        admin: legacy.admin,
+        commission_rate: legacy.commission_rate,
+        pairs: legacy.pairs,
+        buy_token: legacy.buy_token,
+        banner_url: legacy.banner_url,
+        description: legacy.description,
+        title: legacy.title,
+        start_time: legacy.start_time,
+        end_time: legacy.end_time,
+        oracle: legacy.oracle,
+        resolution_source: legacy.resolution_source,
+        asset_to_track: legacy.asset_to_track,
+        second_asset_to_track: None,
+        market_type: legacy.market_type,
+        target_price: legacy.target_price,
+        initial_price: legacy.initial_price,
+        second_initial_price: None,
+        resolution_deadline: legacy.end_time.plus_seconds(DEFAULT_RESOLUTION_TIMEOUT),
+        max_price_staleness: None,
+        resolution_mode: ResolutionMode::AdminOnly,
+        resolution_bounty: Uint128::zero(),
+        dispute_config: None,
+        resolvers: None,
+        oracle_quorum: None,
+        twap_window: None,
+        pricing_mode: PricingMode::Parimutuel,
+        tax_config: TaxConfig::default(),
+    };
+    CONFIG.save(storage, &config)
+}
+
+/// Moves the two legacy option totals to OPTION_TOTALS and rebuilds SHARE_TOTALS
+fn migrate_legacy_market_state(storage: &mut dyn Storage) -> StdResult<()> {
+    let config = CONFIG.load(storage)?;
+    let legacy = LEGACY_MARKET_STATE.load(storage)?;
+
+    let legacy_totals = [legacy.total_stake_option_a, legacy.total_stake_option_b];
+    for (option, total) in config.pairs.iter().zip(legacy_totals) {
+        OPTION_TOTALS.save(storage, &option.text, &total)?;
+    }
+
+    // Positions already paid out are no longer outstanding
+    let mut share_totals = vec![Uint128::zero(); config.pairs.len()];
+    for item in SHARES.range(storage, None, None, cosmwasm_std::Order::Ascending) {
+        let ((_, option_text), share) = item?;
+        if share.has_withdrawn {
+            continue;
+        }
+        if let Some(index) = config.pairs.iter().position(|p| p.text == option_text) {
+            share_totals[index] += share.amount;
+        }
+    }
+    for (option, total) in config.pairs.iter().zip(share_totals) {
+        SHARE_TOTALS.save(storage, &option.text, &total)?;
+    }
+
+    let market_state = MarketState {
+        status: legacy.status,
+        total_value: legacy.total_value,
+        num_bettors: legacy.num_bettors,
+        volume: legacy.volume,
+        bounty_reserve: Uint128::zero(),
+    };
+    MARKET_STATE.save(storage, &market_state)
+}
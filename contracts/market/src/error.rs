@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Timestamp, Uint128};
 use cw_utils::PaymentError;
 use thiserror::Error;
 
@@ -7,12 +7,226 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Utils(#[from] utils::error::ContractError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Unauthorized: Only the admin can resolve markets")]
+    UnauthorizedResolver {},
+
     #[error("Payment error: {0}")]
     PaymentError(String),
 
     #[error("{0}")]
     Payment(#[from] PaymentError),
+
+    #[error("Market has not started yet. Opens at {start_time}")]
+    MarketNotStarted { start_time: Timestamp },
+
+    #[error("Market is closed for trading. Closed at {end_time}")]
+    MarketClosed { end_time: Timestamp },
+
+    #[error("A market needs between 2 and {max} options, got {count}")]
+    InvalidOptionCount { count: usize, max: usize },
+
+    #[error("Option {option} is listed more than once")]
+    DuplicateOption { option: String },
+
+    #[error("Option {option} can't be used in a token subunit or symbol, use letters, digits, spaces and /:._ only")]
+    InvalidOptionLabel { option: String },
+
+    #[error("Range boundaries must be non-empty and strictly increasing")]
+    InvalidRangeBoundaries {},
+
+    #[error("Only UpDown and Relative markets have a strike price")]
+    NoStrikeForMarketType {},
+
+    #[error("Strike price was never recorded for this market")]
+    StrikePriceNotSet {},
+
+    #[error("Strike price must be greater than zero")]
+    ZeroStrikePrice {},
+
+    #[error("Oracle price at {price_time} is too far from the market start {start_time} to be its strike")]
+    StrikePriceOutOfTolerance {
+        price_time: Timestamp,
+        start_time: Timestamp,
+    },
+
+    #[error("Only Touch markets have a barrier to observe")]
+    NoBarrierForMarketType {},
+
+    #[error("Barrier was already hit at {price_time}, the market can be resolved")]
+    BarrierHit { price_time: Timestamp },
+
+    #[error("Scalar lower_bound must be below upper_bound")]
+    InvalidScalarBounds {},
+
+    #[error("Relative markets need a second_asset_to_track different from asset_to_track")]
+    InvalidSecondAsset {},
+
+    #[error("Resolver threshold must be between 1 and the number of distinct members")]
+    InvalidResolverSet {},
+
+    #[error("Unauthorized: Only the market resolvers can submit an outcome")]
+    UnauthorizedOutcomeSubmitter {},
+
+    #[error("Event markets are resolved by their resolvers, not from an oracle price")]
+    NotPriceMarket {},
+
+    #[error("Markets with resolvers are resolved by their members' votes")]
+    ResolvedByCommittee {},
+
+    #[error("Oracle quorum needs at least one oracle and min_responses between 1 and the number of oracles")]
+    InvalidOracleQuorum {},
+
+    #[error("TWAP window must be positive, fit in the market duration and not be combined with an oracle quorum")]
+    InvalidTwapWindow {},
+
+    #[error("Only markets with a fixed price resolution source take manual prices")]
+    NotManualPriceMarket {},
+
+    #[error("Asset {asset} is not tracked by this market")]
+    UntrackedAsset { asset: String },
+
+    #[error("Market has been cancelled")]
+    MarketCancelled {},
+
+    #[error("Market is void")]
+    MarketVoid {},
+
+    #[error("Refunds are only available for cancelled or void markets")]
+    RefundsNotAvailable {},
+
+    #[error("Resolution deadline not reached yet. Deadline: {deadline}")]
+    ResolutionDeadlineNotReached { deadline: Timestamp },
+
+    #[error("Resolution deadline has passed. Deadline: {deadline}")]
+    ResolutionDeadlinePassed { deadline: Timestamp },
+
+    #[error("No proposed resolution to dispute")]
+    NoProposedResolution {},
+
+    #[error("Challenge period has ended at {challenge_ends}")]
+    ChallengePeriodEnded { challenge_ends: Timestamp },
+
+    #[error("Challenge period has not ended yet. Ends at: {challenge_ends}")]
+    ChallengePeriodNotEnded { challenge_ends: Timestamp },
+
+    #[error("Disputes are not enabled for this market")]
+    DisputesNotEnabled {},
+
+    #[error("Invalid dispute bond. Required: {required}, Sent: {sent}")]
+    InvalidDisputeBond { required: Uint128, sent: Uint128 },
+
+    #[error("Market resolution is not disputed")]
+    NotDisputed {},
+
+    #[error("No oracle price available at or before end time {end_time}")]
+    NoSettlementPrice { end_time: Timestamp },
+
+    #[error("Oracle price is after the market end. Price time: {price_time}, End time: {end_time}")]
+    PriceAfterEndTime {
+        price_time: Timestamp,
+        end_time: Timestamp,
+    },
+
+    #[error("Oracle price too stale. Price time: {price_time}, End time: {end_time}")]
+    PriceTooStale {
+        price_time: Timestamp,
+        end_time: Timestamp,
+    },
+
+    #[error("Invalid refund token: {denom}")]
+    InvalidRefundToken { denom: String },
+
+    #[error("A complete set is the same amount of every outcome token of the market")]
+    InvalidCompleteSet {},
+
+    #[error("Only {outstanding} complete sets are outstanding")]
+    InsufficientCompleteSets { outstanding: Uint128 },
+
+    #[error("LMSR liquidity must be positive")]
+    InvalidLiquidity {},
+
+    #[error("Only LMSR and fixed-odds markets have house liquidity to fund or withdraw")]
+    NoHouseLiquidity {},
+
+    #[error("The LMSR must be funded with {required} before trading")]
+    LmsrNotFunded { required: Uint128 },
+
+    #[error("The LMSR is already funded")]
+    LmsrAlreadyFunded {},
+
+    #[error("The LMSR needs exactly {required} to be funded, sent {sent}")]
+    InvalidLiquidityFunding { required: Uint128, sent: Uint128 },
+
+    #[error("House liquidity can only be withdrawn once the market is resolved, cancelled or void")]
+    LiquidityLocked {},
+
+    #[error("Providing liquidity needs both outcome tokens of the option and buy_token")]
+    InvalidPoolDeposit {},
+
+    #[error("The {option} pool has no liquidity")]
+    EmptyPool { option: String },
+
+    #[error("Only {available} LP shares available")]
+    InsufficientLpShares { available: Uint128 },
+
+    #[error("Amount out {amount_out} is below the minimum {min_amount_out}")]
+    SlippageExceeded {
+        min_amount_out: Uint128,
+        amount_out: Uint128,
+    },
+
+    #[error("Orders need a positive price and amount")]
+    InvalidOrder {},
+
+    #[error("The order needs {required}, sent {sent}")]
+    InvalidOrderFunds { required: Uint128, sent: Uint128 },
+
+    #[error("Order {order_id} not found")]
+    OrderNotFound { order_id: u64 },
+
+    #[error("Tax rate {tax_rate} is above the maximum {max_tax_rate}")]
+    TaxRateExceeded {
+        max_tax_rate: Decimal,
+        tax_rate: Decimal,
+    },
+
+    #[error("Odds {odds} are below the minimum {min_odds}")]
+    OddsBelowMinimum { min_odds: Decimal, odds: Decimal },
+
+    #[error("Only fixed-odds markets have house lines")]
+    NotFixedOddsMarket {},
+
+    #[error("Scalar markets can't use fixed odds")]
+    FixedOddsScalarMarket {},
+
+    #[error("House odds and liability cap must be positive")]
+    InvalidHouseLine {},
+
+    #[error("The house offers no odds on {option}")]
+    NoHouseLine { option: String },
+
+    #[error("Bet would take the liability on {option} to {liability}, above the cap of {liability_cap}")]
+    LiabilityCapExceeded {
+        option: String,
+        liability_cap: Uint128,
+        liability: Uint128,
+    },
+
+    #[error("The house needs {required} to cover its payouts, it has {available}")]
+    InsufficientBankroll { required: Uint128, available: Uint128 },
+
+    #[error("Fixed-odds bets can't be sold back to the house")]
+    FixedOddsSell {},
+
+    #[error("Invalid tax config: rates and cap must be at most 100%, steps strictly increasing and the grace period shorter than the market")]
+    InvalidTaxConfig {},
+
+    #[error("Only {available} outcome tokens of option {option} can be withdrawn")]
+    WithdrawExceedsShares { option: String, available: Uint128 },
 }
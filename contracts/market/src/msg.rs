@@ -4,7 +4,10 @@ use coreum_wasm_sdk::types::{
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 
-use crate::state::{MarketOption, MarketStatus};
+use crate::state::{
+    Dispute, DisputeConfig, FixedOddsBet, MarketOption, MarketStatus, OracleQuorum, Order,
+    OrderSide, PriceSnapshot, ResolutionRecord, ResolverSet,
+};
 
 #[cw_serde]
 pub struct MigrateMsg {}
@@ -20,16 +23,44 @@ pub struct InstantiateMsg {
     pub banner_url: String,
     pub description: String,
     pub title: String,
-    pub resolution_source: String, // NOT FOR NOW 
+    // Rules the outcome is decided by, e.g. a URL or a rules hash. Also selects the price adapter:
+    // "fixed:<price>" for admin posted prices, "wasm:<price path>:<timestamp path>:<query>" for
+    // any contract answering a smart query, anything else for the clp feed
+    pub resolution_source: String,
     // The denom we're tracking on the clp_feed contract
     pub asset_to_track: String, //This is the asset name "CORE", "BTC", "ETH", etc. not the DENOM
+    // Asset asset_to_track is compared against (Relative markets only)
+    pub second_asset_to_track: Option<String>,
     //"up_down" -->  price to beat + duration. example: "Bitcoin Up or Down - 5 min"
     //"price_at" --> "Will Bitcoin be higher then 80k on the 15th of March 2026"
     pub market_type: MarketType,
-    // If we reach this price market resolve to UP or YES
+    // If we reach this price market resolve to YES
+    // UpDown markets ignore it and resolve against the price at start_time instead
     pub target_price: Decimal,
   
     pub oracle: Addr,
+    // Seconds after end_time the market has to be resolved before anyone can void it
+    // Defaults to DEFAULT_RESOLUTION_TIMEOUT
+    pub resolution_timeout: Option<u64>,
+    // Oldest a settlement price may be, in seconds before end_time. None disables the check
+    pub max_price_staleness: Option<u64>,
+    // Who may call Resolve once the market has ended
+    pub resolution_mode: ResolutionMode,
+    // Paid in buy_token to the resolver, set aside from the commissions (Permissionless only)
+    pub resolution_bounty: Uint128,
+    // Challenge period and arbitrator for resolutions. None: resolutions are final immediately
+    pub dispute_config: Option<DisputeConfig>,
+    // Who votes on the outcome, instead of Resolve. None: the admin alone
+    pub resolvers: Option<ResolverSet>,
+    // Settle on the median price of several feeds instead of `oracle` alone
+    pub oracle_quorum: Option<OracleQuorum>,
+    // Settle on the time-weighted average of the prices keepers observe (SnapshotPrice)
+    // during this many seconds before end_time. None: settle on a single price
+    pub twap_window: Option<u64>,
+    // How outcome tokens are priced and paid. None: Parimutuel
+    pub pricing_mode: Option<PricingMode>,
+    // Early-exit tax of SellShare. None: linear from 0% at start_time to 100% at end_time
+    pub tax_config: Option<TaxConfig>,
 }
 
 #[cw_serde]
@@ -37,6 +68,8 @@ pub enum ExecuteMsg {
     BuyShare {
         market_id: String,
         option: String,
+        min_amount_out: Option<Uint128>, // Fewest outcome tokens to receive
+        min_odds: Option<Decimal>,       // Lowest odds of the option once the buy is in
     },
     Resolve {
         market_id: String,
@@ -46,6 +79,125 @@ pub enum ExecuteMsg {
     },
     SellShare {
         option: String,
+        min_amount_out: Option<Uint128>, // Least buy_token to receive, after tax and commission
+        max_tax_rate: Option<Decimal>,   // Highest time-based tax rate accepted
+    },
+    // Admin only, until end_time: stops the market and makes every position refundable
+    CancelMarket {
+        market_id: String,
+    },
+    // Return outcome tokens of a cancelled or void market to get the stake back
+    ClaimRefund {
+        market_id: String,
+    },
+    // Anyone: voids a market that could not be resolved before its resolution deadline
+    VoidMarket {
+        market_id: String,
+    },
+    // Anyone (keeper): records the current oracle price if it is the closest yet to end_time,
+    // for each feed of an oracle quorum
+    SnapshotPrice {
+        market_id: String,
+    },
+    // Anyone: challenges the proposed resolution by posting the dispute bond
+    DisputeResolution {
+        market_id: String,
+    },
+    // Arbitrator only: rules on a disputed resolution
+    ArbitrateDispute {
+        market_id: String,
+        winning_option: String,
+    },
+    // Anyone: makes an unchallenged proposed resolution final once the challenge period is over,
+    // paying the resolution bounty to the resolver
+    FinalizeResolution {
+        market_id: String,
+    },
+    // Anyone (keeper): records the strike price(s) of an UpDown or Relative market. From
+    // STRIKE_PRICE_TOLERANCE before start_time each call moves the strike to the latest price,
+    // from start_time on it is final. Buying or selling records it too if nobody called this first
+    StartMarket {
+        market_id: String,
+    },
+    // Anyone (keeper): checks the current oracle price of a Touch market against its barrier
+    ObservePrice {
+        market_id: String,
+    },
+    // Resolver only: votes for the winning option, resolves once the threshold of resolvers agree
+    SubmitOutcome {
+        market_id: String,
+        winning_option: String,
+    },
+    // Anyone: pays buy_token to get the same amount of every outcome token, without commission.
+    // Complete sets are not parimutuel shares, they are always worth one buy_token
+    MintCompleteSet {
+        market_id: String,
+    },
+    // Anyone: returns the same amount of every outcome token for as much buy_token.
+    // Once the market is resolved (except Scalar markets) winning tokens alone are redeemed 1:1,
+    // up to the outstanding complete sets
+    RedeemCompleteSet {
+        market_id: String,
+    },
+    // Admin only: funds the LMSR of an LMSR market with its max loss, b * ln(number of options),
+    // trading opens once it is funded. Tops up the bankroll of a fixed-odds market
+    FundLiquidity {
+        market_id: String,
+    },
+    // Admin only: takes back the LMSR or bankroll funds not owed to token holders once the market is settled
+    WithdrawLiquidity {
+        market_id: String,
+    },
+    // Admin only: sets the odds and liability cap of an option of a fixed-odds market.
+    // Bets already placed keep their odds
+    SetHouseLine {
+        market_id: String,
+        option: String,
+        odds: Decimal,
+        liability_cap: Uint128,
+    },
+    // Anyone: deposits outcome tokens of `option` and buy_token in the option's AMM pool for LP shares.
+    // The first deposit sets the price, later ones get shares for the smaller side of the pool ratio.
+    // The deposited tokens must be held as shares of the option, complete set tokens can't be pooled
+    ProvideLiquidity {
+        market_id: String,
+        option: String,
+    },
+    // LP: burns LP shares for their part of both pool reserves, allowed at any time
+    RemoveLiquidity {
+        market_id: String,
+        option: String,
+        lp_shares: Uint128,
+    },
+    // Anyone: swaps buy_token for outcome tokens of `option` or back, against its AMM pool.
+    // The commission is taken on the input and stays in the pool as LP fee
+    Swap {
+        market_id: String,
+        option: String,
+        min_amount_out: Option<Uint128>,
+    },
+    // Anyone: places a limit order for `amount` outcome tokens of `option` at `price` buy_token each.
+    // A bid pays amount * price (rounded up) in buy_token, an ask pays the outcome tokens, which must be
+    // held as shares. The order fills against the best opposite orders at their price and the rest stays open.
+    // Orders only match while the market is active
+    PlaceOrder {
+        market_id: String,
+        option: String,
+        side: OrderSide,
+        price: Decimal,
+        amount: Uint128,
+    },
+    // Order owner: cancels an open order and gets its escrow back, allowed at any time
+    CancelOrder {
+        market_id: String,
+        order_id: u64,
+    },
+    // Admin only: posts the price of an asset of a market with a "fixed:<price>" resolution source.
+    // Prices can be posted until end_time
+    SetManualPrice {
+        market_id: String,
+        asset: String,
+        price: Decimal,
     },
 }
 
@@ -80,6 +232,32 @@ pub enum QueryMsg {
         option: String,
         amount: String,
     }, // Simulate selling shares
+    #[returns(ResolutionResponse)]
+    GetResolution { market_id: String }, // Settlement evidence, None until resolved
+    #[returns(PricesResponse)]
+    GetPrices { market_id: String }, // Price of one token of each option, in buy_token
+    #[returns(TwapObservationsResponse)]
+    GetTwapObservations { market_id: String }, // Closing window prices of TWAP markets
+    #[returns(PoolResponse)]
+    GetPool { market_id: String, option: String }, // AMM pool of an option
+    #[returns(LpPositionResponse)]
+    GetLpPosition {
+        market_id: String,
+        option: String,
+        user: Addr,
+    }, // LP shares of a user in an option's pool and what they are worth
+    #[returns(OrderBookResponse)]
+    GetOrderBook {
+        market_id: String,
+        option: String,
+        limit: Option<u32>,
+    }, // Open amount per price level, best prices first
+    #[returns(OpenOrdersResponse)]
+    GetOpenOrders { market_id: String, user: Addr }, // Open orders of a user
+    #[returns(HouseLinesResponse)]
+    GetHouseLines { market_id: String }, // Odds, caps and liabilities of a fixed-odds market
+    #[returns(BetsResponse)]
+    GetBets { market_id: String, user: Addr }, // Fixed-odds bets of a user
 }
 
 // We define a custom struct for each query response
@@ -161,6 +339,7 @@ pub struct MarketStatsResponse {
     pub total_value: Coin,
     pub num_bettors: u64,
     pub options_odds: Vec<OptionOdds>,
+    pub complete_sets: Uint128, // Outstanding complete sets, each backed by one buy_token
     // Legacy fields for backward compatibility
     pub odds_a: Decimal,
     pub odds_b: Decimal,
@@ -186,6 +365,7 @@ pub struct UserWinningsResponse {
 #[cw_serde]
 pub struct TaxRateResponse {
     pub tax_rate: Decimal, // Current tax rate as a decimal (0.0 to 1.0)
+    pub tax_config: TaxConfig, // Curve, cap and grace period of the market
 }
 
 #[cw_serde]
@@ -194,11 +374,217 @@ pub struct SimulateSellResponse {
     pub tax_rate: Decimal,         // Tax rate applied
     pub tax_amount: String,        // Amount taken as tax
     pub amount_after_tax: String,  // Amount user would receive
+    pub tax_config: TaxConfig,     // Curve, cap and grace period of the market
+}
+#[cw_serde]
+pub struct ResolutionResponse {
+    pub resolution: Option<ResolutionRecord>,
+    pub dispute: Option<Dispute>,
+    pub strike: Option<PriceSnapshot>,        // UpDown and Relative markets only
+    pub second_strike: Option<PriceSnapshot>, // Relative markets only
+    pub barrier_hit: Option<PriceSnapshot>,   // Touch markets only
+    pub votes: Vec<OutcomeVote>,              // Open resolver votes of the current round
 }
+
+#[cw_serde]
+pub struct PricesResponse {
+    pub prices: Vec<OptionPrice>,
+}
+
+#[cw_serde]
+pub struct OptionPrice {
+    pub option: String,
+    // LMSR: the cost function price. Parimutuel: the share of the pot staked on the option
+    pub price: Decimal,
+}
+
+#[cw_serde]
+pub struct PoolResponse {
+    pub option: String,
+    pub token_reserve: Uint128,
+    pub buy_token_reserve: Uint128,
+    pub lp_shares: Uint128,
+    pub price: Option<Decimal>, // buy_token per outcome token, None while the pool is empty
+}
+
+#[cw_serde]
+pub struct LpPositionResponse {
+    pub lp_shares: Uint128,
+    pub token_amount: Uint128,     // Outcome tokens the shares would withdraw
+    pub buy_token_amount: Uint128, // buy_token the shares would withdraw
+}
+
+#[cw_serde]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub amount: Uint128, // Outcome tokens open at this price
+}
+
+#[cw_serde]
+pub struct OrderBookResponse {
+    pub option: String,
+    pub bids: Vec<PriceLevel>, // Highest price first
+    pub asks: Vec<PriceLevel>, // Lowest price first
+}
+
+#[cw_serde]
+pub struct OpenOrdersResponse {
+    pub orders: Vec<Order>,
+}
+
+#[cw_serde]
+pub struct OptionHouseLine {
+    pub option: String,
+    pub odds: Option<Decimal>, // None until the admin sets the line
+    pub liability_cap: Uint128,
+    pub liability: Uint128, // Payouts owed if the option wins
+}
+
+#[cw_serde]
+pub struct HouseLinesResponse {
+    pub bankroll: Uint128, // Total deposited by the admin
+    pub lines: Vec<OptionHouseLine>,
+}
+
+#[cw_serde]
+pub struct BetsResponse {
+    pub bets: Vec<FixedOddsBet>,
+}
+
+#[cw_serde]
+pub struct TwapObservationsResponse {
+    pub window_start: Option<Timestamp>, // None if the market doesn't settle on a TWAP
+    pub twap: Option<Decimal>,           // TWAP of asset_to_track so far
+    pub second_twap: Option<Decimal>,    // TWAP of second_asset_to_track (Relative markets only)
+    pub observations: Vec<TwapObservation>,
+}
+
+#[cw_serde]
+pub struct TwapObservation {
+    pub asset: String,
+    pub price: Decimal,
+    pub timestamp: Timestamp, // Timestamp of the oracle price
+}
+
+#[cw_serde]
+pub struct OutcomeVote {
+    pub resolver: Addr,
+    pub option: String,
+}
+
+#[cw_serde]
+pub enum PricingMode {
+    // Tokens are minted 1:1 with the net payment and winners share the pot
+    Parimutuel,
+    // Logarithmic market scoring rule with liquidity `b` in buy_token, funded by the admin.
+    // Trades move the price along the cost function and each winning token pays one buy_token
+    Lmsr { liquidity: Uint128 },
+    // The admin sets the odds and a liability cap per option and funds a bankroll.
+    // Each bet locks its payout at the odds of the moment, as many tokens each paying one buy_token.
+    // Not available for Scalar markets
+    FixedOdds,
+}
+
+#[cw_serde]
+pub enum TaxCurve {
+    // No early-exit tax
+    Disabled,
+    // The same rate for the whole market
+    Flat { rate: Decimal },
+    // From 0% when the grace period ends to 100% at end_time
+    Linear,
+    // (e^(k * t) - 1) / (e^k - 1), with k the steepness (0 to 50) and t the share of the taxed
+    // period elapsed. Stays low for most of the market and rises sharply towards end_time
+    Exponential { steepness: Decimal },
+    // The rate of the last step reached, 0% before the first one
+    Stepwise { steps: Vec<TaxStep> },
+}
+
+#[cw_serde]
+pub struct TaxStep {
+    pub after: u64, // Seconds after start_time, strictly increasing
+    pub rate: Decimal,
+}
+
+#[cw_serde]
+pub struct TaxConfig {
+    pub curve: TaxCurve,
+    pub cap: Option<Decimal>, // Highest tax rate. None: 100%
+    pub grace_period: u64,    // Seconds after start_time without any tax
+}
+
+impl Default for TaxConfig {
+    fn default() -> Self {
+        TaxConfig {
+            curve: TaxCurve::Linear,
+            cap: None,
+            grace_period: 0,
+        }
+    }
+}
+
+impl TaxConfig {
+    // Highest steepness of an Exponential curve
+    pub const MAX_STEEPNESS: u64 = 50;
+
+    /// Rates and cap are at most 100%, steps strictly increase and the grace period ends before end_time
+    pub fn is_valid(&self, market_length: u64) -> bool {
+        let valid_rate = |rate: &Decimal| *rate <= Decimal::one();
+        let valid_curve = match &self.curve {
+            TaxCurve::Disabled | TaxCurve::Linear => true,
+            TaxCurve::Flat { rate } => valid_rate(rate),
+            TaxCurve::Exponential { steepness } => {
+                !steepness.is_zero()
+                    && *steepness <= Decimal::from_ratio(Self::MAX_STEEPNESS, 1u64)
+            }
+            TaxCurve::Stepwise { steps } => {
+                !steps.is_empty()
+                    && steps.iter().all(|step| valid_rate(&step.rate))
+                    && steps.windows(2).all(|pair| pair[0].after < pair[1].after)
+            }
+        };
+
+        valid_curve && self.cap.as_ref().is_none_or(valid_rate) && self.grace_period < market_length
+    }
+}
+
+#[cw_serde]
+pub enum ResolutionMode {
+    AdminOnly,      // Only the admin (relayer) can resolve
+    Permissionless, // Anyone can resolve after end_time and collect the bounty
+}
+
+impl std::fmt::Display for ResolutionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolutionMode::AdminOnly => write!(f, "AdminOnly"),
+            ResolutionMode::Permissionless => write!(f, "Permissionless"),
+        }
+    }
+}
+
 #[cw_serde]
 pub enum MarketType {
     UpDown,
     PriceAt,
+    // One option per price bucket. Boundaries are strictly increasing and each bucket
+    // includes its lower boundary, e.g. [60000, 65000] -> "Under 60000", "60000 to 65000", "65000 or more"
+    Range { boundaries: Vec<Decimal> },
+    // "Yes" if the price touches target_price at any time between start_time and end_time.
+    // The barrier is above the initial price if initial_price <= target_price, below otherwise
+    Touch,
+    // "Long" and "Short" split the pot linearly by where the price lands between the bounds.
+    // At or above upper_bound Long takes everything, at or below lower_bound Short does
+    Scalar {
+        lower_bound: Decimal,
+        upper_bound: Decimal,
+    },
+    // "Outperform" if asset_to_track gains more than second_asset_to_track between
+    // start_time and end_time, in percentage terms
+    Relative,
+    // Free-form question with custom option labels, e.g. sports or governance outcomes.
+    // Resolved by the resolvers submitting the winning option instead of an oracle price
+    Event { options: Vec<String> },
 }
 
 impl std::fmt::Display for MarketType {
@@ -206,33 +592,92 @@ impl std::fmt::Display for MarketType {
         match self {
             MarketType::UpDown => write!(f, "UpDown"),
             MarketType::PriceAt => write!(f, "PriceAt"),
+            MarketType::Range { .. } => write!(f, "Range"),
+            MarketType::Touch => write!(f, "Touch"),
+            MarketType::Scalar { .. } => write!(f, "Scalar"),
+            MarketType::Relative => write!(f, "Relative"),
+            MarketType::Event { .. } => write!(f, "Event"),
         }
     }
 }
 
 impl MarketType {
     /// Returns the option text that wins when the condition is met (price target reached)
-    pub fn get_winning_option_when_target_reached(&self) -> &'static str {
+    /// Range markets have no single target and return None
+    pub fn get_winning_option_when_target_reached(&self) -> Option<&'static str> {
         match self {
-            MarketType::UpDown => "Up",
-            MarketType::PriceAt => "Yes",
+            MarketType::UpDown => Some("Up"),
+            MarketType::PriceAt | MarketType::Touch => Some("Yes"),
+            MarketType::Relative => Some("Outperform"),
+            MarketType::Range { .. } | MarketType::Scalar { .. } | MarketType::Event { .. } => None,
         }
     }
 
     /// Returns the option text that wins when the condition is not met (price target not reached)
-    pub fn get_winning_option_when_target_not_reached(&self) -> &'static str {
+    /// Range markets have no single target and return None
+    pub fn get_winning_option_when_target_not_reached(&self) -> Option<&'static str> {
         match self {
-            MarketType::UpDown => "Down",
-            MarketType::PriceAt => "No",
+            MarketType::UpDown => Some("Down"),
+            MarketType::PriceAt | MarketType::Touch => Some("No"),
+            MarketType::Relative => Some("Underperform"),
+            MarketType::Range { .. } | MarketType::Scalar { .. } | MarketType::Event { .. } => None,
         }
     }
 
-    /// Determines the winning option based on whether target price was reached
-    pub fn determine_winner(&self, current_price: cosmwasm_std::Decimal, target_price: cosmwasm_std::Decimal) -> &'static str {
-        if current_price >= target_price {
+    /// Determines the winning option from the resolution price.
+    /// Binary markets check whether target price was reached, range markets pick the bucket the price falls into
+    pub fn determine_winner(&self, current_price: Decimal, target_price: Decimal) -> String {
+        if let MarketType::Range { boundaries } = self {
+            let bucket = boundaries.iter().filter(|b| current_price >= **b).count();
+            return Self::bucket_label(boundaries, bucket);
+        }
+
+        // Scalar markets pay both sides, the one taking the larger part of the pot is reported
+        if let Some(long_payout) = self.scalar_long_payout(current_price) {
+            let winner = if long_payout >= Decimal::percent(50) { "Long" } else { "Short" };
+            return winner.to_string();
+        }
+
+        let winner = if current_price >= target_price {
             self.get_winning_option_when_target_reached()
         } else {
             self.get_winning_option_when_target_not_reached()
+        };
+        winner.unwrap_or_default().to_string()
+    }
+
+    /// Whether the market resolves from an oracle price, Event markets don't
+    pub fn is_price_based(&self) -> bool {
+        !matches!(self, MarketType::Event { .. })
+    }
+
+    /// Whether the market snapshots its strike price(s) at start_time
+    pub fn has_strike(&self) -> bool {
+        matches!(self, MarketType::UpDown | MarketType::Relative)
+    }
+
+    /// Share of the pot paid to Long holders of a Scalar market, None for other market types
+    pub fn scalar_long_payout(&self, price: Decimal) -> Option<Decimal> {
+        match self {
+            MarketType::Scalar {
+                lower_bound,
+                upper_bound,
+            } => {
+                let clamped = price.clamp(*lower_bound, *upper_bound);
+                Some((clamped - lower_bound) / (*upper_bound - lower_bound))
+            }
+            _ => None,
+        }
+    }
+
+    /// Option text of the bucket at `index`, the edge buckets are open-ended
+    fn bucket_label(boundaries: &[Decimal], index: usize) -> String {
+        if index == 0 {
+            format!("Under {}", boundaries[0])
+        } else if index == boundaries.len() {
+            format!("{} or more", boundaries[index - 1])
+        } else {
+            format!("{} to {}", boundaries[index - 1], boundaries[index])
         }
     }
 
@@ -240,23 +685,14 @@ impl MarketType {
     pub fn get_options(&self) -> Vec<String> {
         match self {
             MarketType::UpDown => vec!["Up".to_string(), "Down".to_string()],
-            MarketType::PriceAt => vec!["Yes".to_string(), "No".to_string()],
+            MarketType::PriceAt | MarketType::Touch => vec!["Yes".to_string(), "No".to_string()],
+            MarketType::Range { boundaries } => (0..=boundaries.len())
+                .map(|index| Self::bucket_label(boundaries, index))
+                .collect(),
+            MarketType::Scalar { .. } => vec!["Long".to_string(), "Short".to_string()],
+            MarketType::Relative => vec!["Outperform".to_string(), "Underperform".to_string()],
+            MarketType::Event { options } => options.clone(),
         }
     }
 
-    /// Creates type-safe odds ensuring correct option assignment
-    /// first_odds: odds for the first option (Up/Yes)
-    /// second_odds: odds for the second option (Down/No)
-    pub fn create_option_odds(&self, first_odds: cosmwasm_std::Decimal, second_odds: cosmwasm_std::Decimal) -> Vec<OptionOdds> {
-        vec![
-            OptionOdds {
-                option: self.get_winning_option_when_target_reached().to_string(),
-                odds: first_odds,
-            },
-            OptionOdds {
-                option: self.get_winning_option_when_target_not_reached().to_string(),
-                odds: second_odds,
-            },
-        ]
-    }
 }
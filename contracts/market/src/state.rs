@@ -1,10 +1,13 @@
 use coreum_wasm_sdk::types::cosmos::base::v1beta1::Coin;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, StdResult, Storage, Timestamp, Uint128};
+use cosmwasm_std::{
+    Addr, Decimal, Decimal256, Empty, StdError, StdResult, Storage, Timestamp, Uint128,
+};
 use cw_storage_plus::{Item, Map};
-use std::str::FromStr;
 
-use crate::msg::MarketType;
+use crate::msg::{MarketType, PricingMode, ResolutionMode, TaxConfig, TaxCurve};
+use utils::lmsr;
+use utils::math::exp_neg;
 
 #[cw_serde]
 pub struct State {
@@ -32,7 +35,20 @@ pub enum MarketStatus {
     Active,                 // Market is open for betting
     Closed,                 // Market has ended, no more bets, awaiting resolution
     Resolved(MarketOption), // Market resolved with winning option
+    ProposedResolution {
+        option: MarketOption,      // Proposed winning option, final once the challenge period ends
+        challenge_ends: Timestamp, // End of the challenge period
+    },
+    Disputed(MarketOption), // Proposed resolution was challenged, awaiting the arbitrator's ruling
     Cancelled,              // Market was cancelled
+    Void,                   // Market could not be resolved before the resolution deadline
+}
+
+impl MarketStatus {
+    /// Cancelled and void markets refund every position instead of paying out winnings
+    pub fn is_refundable(&self) -> bool {
+        matches!(self, MarketStatus::Cancelled | MarketStatus::Void)
+    }
 }
 #[cw_serde]
 pub struct Config {
@@ -47,30 +63,299 @@ pub struct Config {
     pub start_time: Timestamp,    // Start time of the market
     pub end_time: Timestamp,      // End time of the market
     pub oracle: Addr,
-    pub resolution_source: String, // Source of the resolution --> Feed contract address
+    pub resolution_source: String, // Rules of the resolution, also selects the oracle adapter
     pub asset_to_track: String, //This is the asset name "CORE", "BTC", "ETH", etc. not the DENOM
+    pub second_asset_to_track: Option<String>, // Relative markets only
     pub market_type: MarketType,
     pub target_price: Decimal,
     pub initial_price: Decimal, // Initial price at market creation
+    pub second_initial_price: Option<Decimal>, // Initial price of second_asset_to_track
+    pub resolution_deadline: Timestamp, // After this time an unresolved market can be voided
+    pub max_price_staleness: Option<u64>, // Max age of the settlement price at end_time, in seconds
+    pub resolution_mode: ResolutionMode,
+    pub resolution_bounty: Uint128, // Bounty paid to the resolver in buy_token
+    pub dispute_config: Option<DisputeConfig>, // None: resolutions are final immediately
+    pub resolvers: Option<ResolverSet>, // Submit the outcome of Event markets
+    pub oracle_quorum: Option<OracleQuorum>, // Settle on the median of several feeds
+    pub twap_window: Option<u64>, // Settle on the TWAP of this many seconds before end_time
+    pub pricing_mode: PricingMode,
+    pub tax_config: TaxConfig, // Early-exit tax curve of SellShare
 }
 
+#[cw_serde]
+pub struct OracleQuorum {
+    pub oracles: Vec<Addr>,     // Feeds implementing the clp-feed query interface
+    pub min_responses: u32,     // Fresh prices within max_deviation needed to settle
+    pub max_deviation: Decimal, // Max relative distance to the median, e.g. 0.02 = 2%
+}
+
+#[cw_serde]
+pub struct DisputeConfig {
+    pub arbitrator: Addr,      // Rules on disputed resolutions
+    pub challenge_period: u64, // Seconds a proposed resolution can be disputed
+    pub bond: Uint128,         // Bond in buy_token required to dispute
+}
+
+#[cw_serde]
+pub struct ResolverSet {
+    pub members: Vec<Addr>,
+    pub threshold: u32, // Matching votes needed to settle on an outcome
+}
+
+// Outcome each resolver voted for (resolver -> option text)
+pub const OUTCOME_VOTES: Map<&Addr, String> = Map::new("outcome_votes");
+
+#[cw_serde]
+pub struct Dispute {
+    pub challenger: Addr,
+    pub bond: Uint128,
+    pub proposed_option: MarketOption,
+    pub disputed_at: Timestamp,
+    pub ruling: Option<MarketOption>, // Set once the arbitrator has ruled
+}
+
+// Dispute raised against the proposed resolution, if any
+pub const DISPUTE: Item<Dispute> = Item::new("dispute");
+
 #[cw_serde]
 pub struct MarketState {
     pub status: MarketStatus, // Combined status and outcome
     pub total_value: Coin,
-    pub num_bettors: u64,              // Number of unique bettors
-    pub total_stake_option_a: Uint128, // Pre-calculated total for option A
-    pub total_stake_option_b: Uint128, // Pre-calculated total for option B
-    pub volume: Uint128,               // Total volume of the market
+    pub num_bettors: u64,        // Number of unique bettors
+    pub volume: Uint128,         // Total volume of the market
+    pub bounty_reserve: Uint128, // Commissions held back to pay the resolution bounty
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const MARKET_STATE: Item<MarketState> = Item::new("market_state");
 
+// Config layout written before resolution, pricing and tax settings existed. Only read by `migrate`
+#[cw_serde]
+pub struct LegacyConfig {
+    pub id: String,
+    pub admin: Addr,
+    pub commission_rate: Uint128,
+    pub pairs: Vec<MarketOption>,
+    pub buy_token: String,
+    pub banner_url: String,
+    pub description: String,
+    pub title: String,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub oracle: Addr,
+    pub resolution_source: String,
+    pub asset_to_track: String,
+    pub market_type: MarketType,
+    pub target_price: Decimal,
+    pub initial_price: Decimal,
+}
+
+// Market state layout written before markets could have more than two options. Only read by `migrate`
+#[cw_serde]
+pub struct LegacyMarketState {
+    pub status: MarketStatus,
+    pub total_value: Coin,
+    pub num_bettors: u64,
+    pub total_stake_option_a: Uint128,
+    pub total_stake_option_b: Uint128,
+    pub volume: Uint128,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+pub const LEGACY_MARKET_STATE: Item<LegacyMarketState> = Item::new("market_state");
+
+// Outstanding complete sets. Each one is backed by one buy_token kept outside the parimutuel pot,
+// any holder of the tokens redeems against this collateral
+pub const COMPLETE_SETS: Item<Uint128> = Item::new("complete_sets");
+
+// Tokens the LMSR has sold and not yet paid or refunded, per option (LMSR markets only)
+pub const LMSR_QUANTITIES: Map<&str, Uint128> = Map::new("lmsr_quantities");
+
+// Amount the admin funded the LMSR with, None until it is funded
+pub const LMSR_SUBSIDY: Item<Uint128> = Item::new("lmsr_subsidy");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AmmPool {
+    pub token_reserve: Uint128,     // Outcome tokens of the option held by the pool
+    pub buy_token_reserve: Uint128, // buy_token held by the pool, outside the market pot
+    pub lp_shares: Uint128,         // Total LP shares issued
+}
+
+impl AmmPool {
+    /// Constant product output for `amount_in`, after the commission kept in the pool as LP fee.
+    /// `buying`: buy_token in and outcome tokens out, otherwise the reverse
+    pub fn amount_out(&self, amount_in: Uint128, buying: bool, commission_rate: Uint128) -> Uint128 {
+        let (reserve_in, reserve_out) = if buying {
+            (self.buy_token_reserve, self.token_reserve)
+        } else {
+            (self.token_reserve, self.buy_token_reserve)
+        };
+        let net_in = amount_in - amount_in * commission_rate / Uint128::from(10000u128);
+
+        reserve_out.multiply_ratio(net_in, reserve_in + net_in)
+    }
+
+    /// Price of one outcome token in buy_token, None while the pool is empty
+    pub fn price(&self) -> Option<Decimal> {
+        (!self.token_reserve.is_zero())
+            .then(|| Decimal::from_ratio(self.buy_token_reserve, self.token_reserve))
+    }
+}
+
+// Outcome token / buy_token pool per option (option_text -> pool)
+pub const AMM_POOLS: Map<&str, AmmPool> = Map::new("amm_pools");
+
+// LP shares per (provider, option_text)
+pub const LP_SHARES: Map<(&Addr, &str), Uint128> = Map::new("lp_shares");
+
+#[cw_serde]
+pub enum OrderSide {
+    Bid, // Buys outcome tokens, escrows buy_token
+    Ask, // Sells outcome tokens, escrows them
+}
+
+#[cw_serde]
+pub struct Order {
+    pub id: u64,
+    pub owner: Addr,
+    pub option: String,
+    pub side: OrderSide,
+    pub price: Decimal,  // buy_token per outcome token
+    pub amount: Uint128, // Outcome tokens left to fill
+    pub escrow: Uint128, // buy_token still held for a bid, zero for asks
+    pub created_at: Timestamp,
+}
+
+impl Order {
+    /// Key of the order in its side of the book. Bids are keyed by inverted price
+    /// so both sides iterate best price first, then oldest first
+    pub fn book_key(&self) -> (&str, u128, u64) {
+        let atomics = self.price.atomics().u128();
+        let price_key = match self.side {
+            OrderSide::Bid => u128::MAX - atomics,
+            OrderSide::Ask => atomics,
+        };
+        (&self.option, price_key, self.id)
+    }
+
+    /// Whether the order can't fill any further. Rounded up fills can spend a bid's escrow
+    /// before its amount, it then has nothing left to pay with
+    pub fn is_filled(&self) -> bool {
+        self.amount.is_zero() || (self.side == OrderSide::Bid && self.escrow.is_zero())
+    }
+}
+
+// Open limit orders by id
+pub const ORDERS: Map<u64, Order> = Map::new("orders");
+pub const NEXT_ORDER_ID: Item<u64> = Item::new("next_order_id");
+
+// Price-time priority indexes of the open orders: (option_text, price key, order id)
+pub const BIDS: Map<(&str, u128, u64), Empty> = Map::new("bids");
+pub const ASKS: Map<(&str, u128, u64), Empty> = Map::new("asks");
+
+// Open order ids per owner
+pub const USER_ORDERS: Map<(&Addr, u64), Empty> = Map::new("user_orders");
+
+#[cw_serde]
+pub struct HouseLine {
+    pub odds: Decimal,          // Profit per buy_token staked, a winning bet pays stake * (1 + odds)
+    pub liability_cap: Uint128, // Most the house will owe on the option
+}
+
+// House odds and liability cap per option (fixed-odds markets only)
+pub const HOUSE_LINES: Map<&str, HouseLine> = Map::new("house_lines");
+
+// Payouts the house owes per option, one buy_token per outstanding token (fixed-odds markets only)
+pub const LIABILITIES: Map<&str, Uint128> = Map::new("liabilities");
+
+// Total buy_token the admin deposited in the house bankroll (fixed-odds markets only)
+pub const BANKROLL: Item<Uint128> = Item::new("bankroll");
+
+#[cw_serde]
+pub struct FixedOddsBet {
+    pub id: u64,
+    pub option: String,
+    pub stake: Uint128,  // Net of commission
+    pub odds: Decimal,   // House odds when the bet was placed
+    pub payout: Uint128, // Locked payout if the option wins, as many tokens are minted
+    pub placed_at: Timestamp,
+}
+
+// Fixed-odds bets per (bettor, bet id)
+pub const BETS: Map<(&Addr, u64), FixedOddsBet> = Map::new("bets");
+pub const NEXT_BET_ID: Item<u64> = Item::new("next_bet_id");
+
+/// Tokens the house owes per option: the LMSR quantities or the fixed-odds liabilities.
+/// None for parimutuel markets, where winners share the pot instead
+pub fn house_positions(pricing_mode: &PricingMode) -> Option<Map<&'static str, Uint128>> {
+    match pricing_mode {
+        PricingMode::Parimutuel => None,
+        PricingMode::Lmsr { .. } => Some(LMSR_QUANTITIES),
+        PricingMode::FixedOdds => Some(LIABILITIES),
+    }
+}
+
+// Pre-calculated total net stake per option (option_text -> amount)
+pub const OPTION_TOTALS: Map<&str, Uint128> = Map::new("option_totals");
+
+// Running total of outstanding shares per option (option_text -> amount), pooled and listed ones included
+pub const SHARE_TOTALS: Map<&str, Uint128> = Map::new("share_totals");
+
 // Map with composite key: (user_address, option_text) -> Share
 // This allows O(1) lookups and efficient queries
 pub const SHARES: Map<(&Addr, &str), Share> = Map::new("shares");
 
+#[cw_serde]
+pub struct PriceSnapshot {
+    pub price: Decimal,
+    pub timestamp: Timestamp, // Timestamp of the oracle price, not of the block
+}
+
+// Oracle price closest to, and not after, end_time. Used to settle the market
+pub const SETTLEMENT_PRICE: Item<PriceSnapshot> = Item::new("settlement_price");
+
+// First oracle price taken once an UpDown market has started. Up/Down resolve against it
+pub const STRIKE_PRICE: Item<PriceSnapshot> = Item::new("strike_price");
+
+// Strike and settlement prices of second_asset_to_track (Relative markets only)
+pub const SECOND_STRIKE_PRICE: Item<PriceSnapshot> = Item::new("second_strike_price");
+pub const SECOND_SETTLEMENT_PRICE: Item<PriceSnapshot> = Item::new("second_settlement_price");
+
+// Keeper snapshot of each quorum feed per (asset, feed), the price closest to end_time
+pub const QUORUM_SNAPSHOTS: Map<(&str, &Addr), PriceSnapshot> = Map::new("quorum_snapshots");
+
+// Keeper observed prices per (asset, oracle timestamp in nanos) during the TWAP window
+pub const TWAP_OBSERVATIONS: Map<(&str, u64), Decimal> = Map::new("twap_observations");
+
+// Admin posted price per asset, for markets with a fixed price resolution source
+pub const MANUAL_PRICES: Map<&str, PriceSnapshot> = Map::new("manual_prices");
+
+// First observed oracle price that touched the barrier of a Touch market
+pub const BARRIER_HIT: Item<PriceSnapshot> = Item::new("barrier_hit");
+
+#[cw_serde]
+pub struct ResolutionRecord {
+    pub winning_option: MarketOption,
+    pub price: Decimal,             // Oracle price the market settled on, zero for Event markets
+    pub price_timestamp: Timestamp, // Time of the oracle price
+    pub oracle: Addr,
+    pub resolver: Addr,       // Address that executed the resolution
+    pub block_height: u64,    // Block the resolution happened in
+    pub resolved_at: Timestamp,
+    pub initial_price: Decimal,
+    pub target_price: Decimal,
+    pub scalar_payout: Option<Decimal>, // Share of the pot paid to Long (Scalar markets only)
+    pub second_price: Option<Decimal>,  // Settlement price of the second asset (Relative markets only)
+}
+
+// Settlement evidence, saved when the market is resolved
+pub const RESOLUTION: Item<ResolutionRecord> = Item::new("resolution");
+
+// Refund rate per option (option_text -> buy_token paid back per outcome token),
+// snapshotted when the market is cancelled or voided
+pub const REFUND_RATES: Map<&str, Decimal> = Map::new("refund_rates");
+
 #[cw_serde]
 pub struct Share {
     pub amount: Uint128, // Amount of tokens held for this option
@@ -91,117 +376,395 @@ pub struct UserWinningsResponse {
 }
 
 impl MarketState {
-    /// Calculate the total stakes for each option (now uses pre-calculated values)
-    pub fn total_stakes(&self, _config: &Config) -> (Uint128, Uint128) {
-        (self.total_stake_option_a, self.total_stake_option_b)
+    /// Works out the live status of the market from the block time.
+    /// Pending before `start_time`, Active until `end_time`, Closed afterwards.
+    /// A proposed resolution becomes Resolved at the end of its challenge period.
+    /// Terminal statuses (Resolved, Disputed, Cancelled, Void) are never overridden.
+    pub fn effective_status(&self, config: &Config, current_time: Timestamp) -> MarketStatus {
+        match &self.status {
+            MarketStatus::Pending | MarketStatus::Active | MarketStatus::Closed => {
+                if current_time < config.start_time {
+                    MarketStatus::Pending
+                } else if current_time < config.end_time {
+                    MarketStatus::Active
+                } else {
+                    MarketStatus::Closed
+                }
+            }
+            // An unchallenged proposal becomes final once the challenge period is over
+            MarketStatus::ProposedResolution {
+                option,
+                challenge_ends,
+            } if current_time >= *challenge_ends => MarketStatus::Resolved(option.clone()),
+            status => status.clone(),
+        }
     }
 
-    pub fn calculate_odds(&self, _config: &Config) -> (Decimal, Decimal) {
-        let total_a = self.total_stake_option_a;
-        let total_b = self.total_stake_option_b;
+    /// Moves the stored status forward to the live status
+    pub fn refresh_status(&mut self, config: &Config, current_time: Timestamp) {
+        self.status = self.effective_status(config, current_time);
+    }
 
-        let odds_a = if total_a.is_zero() {
-            Decimal::zero()
-        } else {
-            Decimal::from_ratio(total_b, total_a)
-        };
+    /// Calculate the total stakes for each option, in the order of `config.pairs`
+    pub fn total_stakes(&self, storage: &dyn Storage, config: &Config) -> StdResult<Vec<Uint128>> {
+        config
+            .pairs
+            .iter()
+            .map(|option| {
+                Ok(OPTION_TOTALS
+                    .may_load(storage, &option.text)?
+                    .unwrap_or_default())
+            })
+            .collect()
+    }
 
-        let odds_b = if total_b.is_zero() {
-            Decimal::zero()
-        } else {
-            Decimal::from_ratio(total_a, total_b)
-        };
+    /// Odds of each option, in the order of `config.pairs`.
+    /// The odds of an option are the stakes on every other option divided by its own stake.
+    /// With LMSR they are the profit per token at its price: (1 - price) / price.
+    /// With fixed odds they are the house odds
+    pub fn calculate_odds(&self, storage: &dyn Storage, config: &Config) -> StdResult<Vec<Decimal>> {
+        if let PricingMode::FixedOdds = config.pricing_mode {
+            return config
+                .pairs
+                .iter()
+                .map(|option| {
+                    Ok(HOUSE_LINES
+                        .may_load(storage, &option.text)?
+                        .map(|line| line.odds)
+                        .unwrap_or_default())
+                })
+                .collect();
+        }
+        if let PricingMode::Lmsr { .. } = config.pricing_mode {
+            return Ok(self
+                .calculate_prices(storage, config)?
+                .into_iter()
+                .map(|price| {
+                    if price.is_zero() {
+                        Decimal::zero()
+                    } else {
+                        (Decimal::one() - price) / price
+                    }
+                })
+                .collect());
+        }
 
-        (odds_a, odds_b)
+        let totals = self.total_stakes(storage, config)?;
+        let total: Uint128 = totals.iter().sum();
+
+        Ok(totals
+            .iter()
+            .map(|stake| {
+                if stake.is_zero() {
+                    Decimal::zero()
+                } else {
+                    Decimal::from_ratio(total - stake, *stake)
+                }
+            })
+            .collect())
     }
 
-    /// Create type-safe odds using market type to ensure correct option assignment
-    pub fn create_type_safe_odds(&self, config: &Config) -> Vec<crate::msg::OptionOdds> {
-        let (odds_a, odds_b) = self.calculate_odds(config);
-        config.market_type.create_option_odds(odds_a, odds_b)
+    /// Create type-safe odds, each one labelled with the option it belongs to
+    pub fn create_type_safe_odds(
+        &self,
+        storage: &dyn Storage,
+        config: &Config,
+    ) -> StdResult<Vec<crate::msg::OptionOdds>> {
+        let odds = self.calculate_odds(storage, config)?;
+
+        Ok(config
+            .pairs
+            .iter()
+            .zip(odds)
+            .map(|(option, odds)| crate::msg::OptionOdds {
+                option: option.text.clone(),
+                odds,
+            })
+            .collect())
     }
 
+    /// Winnings the user would get for each option if it won, in the order of `config.pairs`
     pub fn calculate_potential_winnings(
         &self,
         storage: &dyn Storage,
         user: &Addr,
         config: &Config,
-    ) -> StdResult<(Coin, Coin)> {
-        let (odds_a, odds_b) = self.calculate_odds(config);
-
-        // Load user stakes from Map - O(1) lookups
-        let user_stake_a = SHARES
-            .may_load(storage, (user, &config.pairs[0].text))?
-            .map(|s| s.amount)
-            .unwrap_or_default();
+    ) -> StdResult<Vec<Coin>> {
+        let odds = self.calculate_odds(storage, config)?;
+
+        config
+            .pairs
+            .iter()
+            .zip(odds)
+            .map(|(option, odds)| {
+                // Load user stake from Map - O(1) lookup
+                let user_stake = SHARES
+                    .may_load(storage, (user, &option.text))?
+                    .map(|s| s.amount)
+                    .unwrap_or_default();
+
+                // Commission is now taken during buy/sell operations, so no need to apply it here.
+                // LMSR and fixed-odds tokens pay one buy_token each
+                let winnings = match config.pricing_mode {
+                    PricingMode::Parimutuel => user_stake + user_stake.mul_floor(odds),
+                    PricingMode::Lmsr { .. } | PricingMode::FixedOdds => user_stake,
+                };
+
+                Ok(Coin {
+                    denom: config.buy_token.clone(),
+                    amount: winnings.to_string(),
+                })
+            })
+            .collect()
+    }
 
-        let user_stake_b = SHARES
-            .may_load(storage, (user, &config.pairs[1].text))?
-            .map(|s| s.amount)
-            .unwrap_or_default();
+    /// Calculate the actual winnings for a user based on the market outcome
+    pub fn calculate_winnings(
+        &self,
+        storage: &dyn Storage,
+        user: &Addr,
+        config: &Config,
+    ) -> StdResult<Coin> {
+        match &self.status {
+            MarketStatus::Resolved(_) => {
+                let tokens = config
+                    .pairs
+                    .iter()
+                    .map(|option| {
+                        Ok(SHARES
+                            .may_load(storage, (user, &option.text))?
+                            .map(|s| s.amount)
+                            .unwrap_or_default())
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
 
-        // Commission is now taken during buy/sell operations, so no need to apply it here
-        let user_stake_a_after_commission = Decimal::from_str(&user_stake_a.to_string())
-            .unwrap_or_default();
+                Ok(Coin {
+                    denom: config.buy_token.clone(),
+                    amount: self.resolved_payout(storage, config, &tokens)?.to_string(),
+                })
+            }
+            MarketStatus::Cancelled | MarketStatus::Void => {
+                self.calculate_refund(storage, user, config)
+            }
+            _ => Ok(Coin {
+                denom: self.total_value.denom.clone(),
+                amount: "0".to_string(),
+            }),
+        }
+    }
 
-        let user_stake_b_after_commission = Decimal::from_str(&user_stake_b.to_string())
-            .unwrap_or_default();
+    /// What `tokens` of each option, in the order of `config.pairs`, pay once the market is resolved.
+    /// Parimutuel holders of an option share its part of the pot. The part of a Scalar side
+    /// nobody staked on goes to the other side
+    pub fn resolved_payout(
+        &self,
+        storage: &dyn Storage,
+        config: &Config,
+        tokens: &[Uint128],
+    ) -> StdResult<Uint128> {
+        let payouts = self.token_payouts(storage, config)?;
+        if config.pricing_mode != PricingMode::Parimutuel {
+            return Ok(tokens
+                .iter()
+                .zip(payouts)
+                .map(|(tokens, payout)| tokens.mul_floor(payout))
+                .sum());
+        }
 
-        let winnings_a = Decimal::from_str(&user_stake_a_after_commission.to_string())
-            .unwrap_or_default()
-            * Decimal::from_str(&odds_a.to_string()).unwrap_or_default();
+        let totals = self.total_stakes(storage, config)?;
+        let pot: Uint128 = totals.iter().sum();
+        let staked_payouts = payouts
+            .iter()
+            .zip(&totals)
+            .filter(|(_, total)| !total.is_zero())
+            .fold(Decimal::zero(), |sum, (payout, _)| sum + payout);
+        if staked_payouts.is_zero() {
+            return Ok(Uint128::zero());
+        }
 
-        let winnings_b = Decimal::from_str(&user_stake_b_after_commission.to_string())
-            .unwrap_or_default()
-            * Decimal::from_str(&odds_b.to_string()).unwrap_or_default();
+        let mut winnings = Uint128::zero();
+        for ((tokens, payout), total) in tokens.iter().zip(payouts).zip(totals) {
+            if total.is_zero() {
+                continue;
+            }
+            winnings += pot
+                .mul_floor(payout / staked_payouts)
+                .multiply_ratio(*tokens, total);
+        }
+        Ok(winnings)
+    }
 
-        let winnings_a = Coin {
-            denom: config.buy_token.clone(),
-            amount: (winnings_a + user_stake_a_after_commission).to_string(),
+    /// What one token of each option pays once the market is resolved, in the order of `config.pairs`.
+    /// Scalar markets split one buy_token between Long and Short, others pay it to the winner
+    pub fn token_payouts(&self, storage: &dyn Storage, config: &Config) -> StdResult<Vec<Decimal>> {
+        let MarketStatus::Resolved(winning_option) = &self.status else {
+            return Ok(vec![Decimal::zero(); config.pairs.len()]);
         };
 
-        let winnings_b = Coin {
-            denom: config.buy_token.clone(),
-            amount: (winnings_b + user_stake_b_after_commission).to_string(),
-        };
+        let scalar_payout = RESOLUTION
+            .may_load(storage)?
+            .and_then(|resolution| resolution.scalar_payout);
+        if let Some(long_payout) = scalar_payout {
+            return Ok(vec![long_payout, Decimal::one() - long_payout]);
+        }
 
-        Ok((winnings_a, winnings_b))
+        Ok(config
+            .pairs
+            .iter()
+            .map(|option| {
+                if option.text == winning_option.text {
+                    Decimal::one()
+                } else {
+                    Decimal::zero()
+                }
+            })
+            .collect())
     }
 
-    /// Calculate the actual winnings for a user based on the market outcome
-    pub fn calculate_winnings(
+    /// Price of one token of each option, in the order of `config.pairs`.
+    /// LMSR: the cost function prices. Parimutuel: the share of the pot staked on the option.
+    /// Fixed odds: the probability implied by the house odds, 1 / (1 + odds)
+    pub fn calculate_prices(&self, storage: &dyn Storage, config: &Config) -> StdResult<Vec<Decimal>> {
+        match &config.pricing_mode {
+            PricingMode::Lmsr { liquidity } => lmsr::prices(
+                &self.lmsr_quantities(storage, config)?,
+                Decimal256::from_ratio(*liquidity, 1u128),
+            )
+            .into_iter()
+            .map(|price| {
+                Decimal::try_from(price).map_err(|_| StdError::generic_err("LMSR price out of range"))
+            })
+            .collect(),
+            PricingMode::FixedOdds => Ok(self
+                .calculate_odds(storage, config)?
+                .into_iter()
+                .map(|odds| Decimal::one() / (Decimal::one() + odds))
+                .collect()),
+            PricingMode::Parimutuel => {
+                let totals = self.total_stakes(storage, config)?;
+                let total: Uint128 = totals.iter().sum();
+
+                Ok(totals
+                    .iter()
+                    .map(|stake| {
+                        if total.is_zero() {
+                            Decimal::from_ratio(1u128, config.pairs.len() as u128)
+                        } else {
+                            Decimal::from_ratio(*stake, total)
+                        }
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Outstanding LMSR tokens of each option, in the order of `config.pairs`
+    pub fn lmsr_quantities(&self, storage: &dyn Storage, config: &Config) -> StdResult<Vec<Decimal256>> {
+        config
+            .pairs
+            .iter()
+            .map(|option| {
+                let quantity = LMSR_QUANTITIES
+                    .may_load(storage, &option.text)?
+                    .unwrap_or_default();
+                Ok(Decimal256::from_ratio(quantity, 1u128))
+            })
+            .collect()
+    }
+
+    /// Tokens of option `index` the LMSR sells for `amount` of buy_token
+    pub fn lmsr_buy_quantity(
+        &self,
+        storage: &dyn Storage,
+        config: &Config,
+        liquidity: Uint128,
+        index: usize,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let quantity = lmsr::quantity_for_cost(
+            &self.lmsr_quantities(storage, config)?,
+            Decimal256::from_ratio(liquidity, 1u128),
+            index,
+            Decimal256::from_ratio(amount, 1u128),
+        )?;
+
+        Uint128::try_from(quantity.to_uint_floor())
+            .map_err(|_| StdError::generic_err("LMSR quantity out of range"))
+    }
+
+    /// Buy_token the LMSR pays back for `amount` tokens of option `index`: C(q) - C(q - amount)
+    pub fn lmsr_sell_value(
+        &self,
+        storage: &dyn Storage,
+        config: &Config,
+        liquidity: Uint128,
+        index: usize,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let liquidity = Decimal256::from_ratio(liquidity, 1u128);
+        let quantities = self.lmsr_quantities(storage, config)?;
+        let cost_before = lmsr::cost(&quantities, liquidity)?;
+
+        let mut quantities_after = quantities;
+        quantities_after[index] = quantities_after[index]
+            .checked_sub(Decimal256::from_ratio(amount, 1u128))
+            .map_err(|_| StdError::generic_err("More tokens sold than the LMSR has outstanding"))?;
+        let value = cost_before
+            .checked_sub(lmsr::cost(&quantities_after, liquidity)?)
+            .unwrap_or_default();
+
+        Uint128::try_from(value.to_uint_floor())
+            .map_err(|_| StdError::generic_err("LMSR value out of range"))
+    }
+
+    /// Holds back part of a commission until the resolution bounty is funded.
+    /// Returns the amount kept in the contract, the rest goes to the admin.
+    pub fn reserve_bounty(&mut self, config: &Config, commission_amount: Uint128) -> Uint128 {
+        if config.resolution_mode != ResolutionMode::Permissionless {
+            return Uint128::zero();
+        }
+
+        let missing = config.resolution_bounty.saturating_sub(self.bounty_reserve);
+        let reserved = missing.min(commission_amount);
+        self.bounty_reserve += reserved;
+        reserved
+    }
+
+    /// Calculate the refund owed to a user for all their remaining shares of a cancelled or void market
+    pub fn calculate_refund(
         &self,
         storage: &dyn Storage,
         user: &Addr,
         config: &Config,
     ) -> StdResult<Coin> {
-        let (winnings_a, winnings_b) = self.calculate_potential_winnings(storage, user, config)?;
-
-        match &self.status {
-            MarketStatus::Resolved(winning_option) => {
-                if winning_option.text == config.pairs[0].text {
-                    Ok(winnings_a)
-                } else if winning_option.text == config.pairs[1].text {
-                    Ok(winnings_b)
-                } else {
-                    Ok(Coin {
-                        denom: config.buy_token.clone(),
-                        amount: "0".to_string(),
-                    })
-                }
+        let mut refund = Uint128::zero();
+
+        if self.status.is_refundable() {
+            for option in &config.pairs {
+                let share_amount = SHARES
+                    .may_load(storage, (user, &option.text))?
+                    .map(|s| s.amount)
+                    .unwrap_or_default();
+                let rate = REFUND_RATES
+                    .may_load(storage, &option.text)?
+                    .unwrap_or_default();
+
+                refund += share_amount.mul_floor(rate);
             }
-            _ => Ok(Coin {
-                denom: self.total_value.denom.clone(),
-                amount: "0".to_string(),
-            }),
         }
+
+        Ok(Coin {
+            denom: config.buy_token.clone(),
+            amount: refund.to_string(),
+        })
     }
 
     /// Calculate time-based tax percentage for selling shares
-    /// Formula: market_length_in_sec - (market_length_in_sec - 1 * elapsed_time)
+    /// Follows the tax curve of the market, zero during the grace period and never above the cap
     /// Returns a tax percentage between 0.0 and 1.0
-    /// The closer to market end, the higher the tax
     pub fn calculate_time_based_tax(&self, config: &Config, current_time: Timestamp) -> Decimal {
+        let tax_config = &config.tax_config;
+
         // Calculate total market duration in seconds
         let market_length_sec = config.end_time.seconds() - config.start_time.seconds();
 
@@ -210,28 +773,48 @@ impl MarketState {
             return Decimal::zero();
         }
 
-        // Calculate elapsed time from start
-        let elapsed_sec = if current_time.seconds() > config.start_time.seconds() {
-            current_time.seconds() - config.start_time.seconds()
+        // Calculate elapsed time from start, capped at the market end
+        let elapsed_sec = current_time
+            .seconds()
+            .saturating_sub(config.start_time.seconds())
+            .min(market_length_sec);
+        if elapsed_sec < tax_config.grace_period {
+            return Decimal::zero();
+        }
+
+        // Share of the taxed period (after the grace period) elapsed, from 0.0 to 1.0
+        let taxed_length_sec = market_length_sec.saturating_sub(tax_config.grace_period);
+        let progress = if taxed_length_sec == 0 {
+            Decimal::one()
         } else {
-            0 // Market hasn't started yet
+            Decimal::from_ratio(elapsed_sec - tax_config.grace_period, taxed_length_sec)
         };
 
-        // If current time is beyond market end, use full market length
-        let elapsed_sec = elapsed_sec.min(market_length_sec);
-
-        // Calculate tax using the formula: market_length - (market_length - elapsed)
-        // This simplifies to: elapsed_sec / market_length_sec
-        // But following your specific formula structure:
-        let tax_factor = market_length_sec - (market_length_sec - elapsed_sec);
-
-        // Convert to percentage (0.0 to 1.0)
-        // At market start: tax_factor = 0, so tax = 0%
-        // At market end: tax_factor = market_length_sec, so tax = 100%
-        let tax_percentage = Decimal::from_ratio(tax_factor, market_length_sec);
+        let tax_percentage = match &tax_config.curve {
+            TaxCurve::Disabled => Decimal::zero(),
+            TaxCurve::Flat { rate } => *rate,
+            TaxCurve::Linear => progress,
+            // (e^(k * t) - 1) / (e^k - 1) = (e^(-k * (1 - t)) - e^(-k)) / (1 - e^(-k))
+            TaxCurve::Exponential { steepness } => {
+                let steepness = Decimal256::from(*steepness);
+                let progress = Decimal256::from(progress);
+                let floor = exp_neg(steepness);
+                let rate = (exp_neg(steepness * (Decimal256::one() - progress)) - floor)
+                    / (Decimal256::one() - floor);
+                Decimal::try_from(rate).unwrap_or(Decimal::one())
+            }
+            TaxCurve::Stepwise { steps } => steps
+                .iter()
+                .rev()
+                .find(|step| step.after <= elapsed_sec)
+                .map(|step| step.rate)
+                .unwrap_or_default(),
+        };
 
-        // Cap at maximum 100% tax
-        tax_percentage.min(Decimal::one())
+        // Cap at the configured maximum, 100% at most
+        tax_percentage
+            .min(tax_config.cap.unwrap_or(Decimal::one()))
+            .min(Decimal::one())
     }
 
     /// Calculate the sell amount after applying time-based tax
@@ -54,9 +54,17 @@ mod tests {
     use cosmwasm_schema::cw_serde;
     use cosmwasm_std::{coin, Addr, Decimal, Timestamp, Uint128};
     use market::msg::{
-        AllSharesResponse, ExecuteMsg, MarketResponse, MarketStatsResponse, MarketType, OddsResponse, QueryMsg, SimulateSellResponse, TaxRateResponse, TotalSharesPerOptionResponse, TotalValueResponse, UserPotentialWinningsResponse, UserWinningsResponse
+        AllSharesResponse, BetsResponse, ExecuteMsg, HouseLinesResponse, MarketResponse, MarketStatsResponse, MarketType, LpPositionResponse, OddsResponse, OpenOrdersResponse, OrderBookResponse, PoolResponse, PriceLevel, PricesResponse, PricingMode, QueryMsg, ResolutionMode, ResolutionResponse, SimulateSellResponse, TaxConfig, TaxCurve, TaxRateResponse, TaxStep, TotalSharesPerOptionResponse, TotalValueResponse, TwapObservationsResponse, UserPotentialWinningsResponse, UserWinningsResponse
+    };
+    use coreum_wasm_sdk::types::cosmos::base::v1beta1::Coin as CoreumCoin;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use market::contract::{migrate, DEFAULT_RESOLUTION_TIMEOUT, STRIKE_PRICE_TOLERANCE};
+    use market::msg::MigrateMsg;
+    use market::state::{
+        DisputeConfig, LegacyConfig, LegacyMarketState, MarketOption, MarketStatus, OracleQuorum,
+        OrderSide, ResolverSet, Share, CONFIG, LEGACY_CONFIG, LEGACY_MARKET_STATE, MARKET_STATE,
+        SHARES, SHARE_TOTALS, STRIKE_PRICE,
     };
-    use market::state::MarketStatus;
     use registry::msg::{
         ExecuteMsg as RegistryExecuteMsg, InstantiateMsg as RegistryInstantiateMsg,
         QueryMsg as RegistryQueryMsg,
@@ -95,6 +103,16 @@ mod tests {
 
     // Helper function to update price in existing CLP feed
     fn update_clp_feed_price(wasm: &Wasm<'_, CoreumTestApp>, admin: &SigningAccount, feed_addr: &str, new_price: &str) {
+        update_clp_feed_asset_price(wasm, admin, feed_addr, "CORE", new_price);
+    }
+
+    // Helper function to update the price of any asset in existing CLP feed
+    fn update_clp_feed_asset_price(wasm: &Wasm<'_, CoreumTestApp>, admin: &SigningAccount, feed_addr: &str, asset: &str, new_price: &str) {
+        update_clp_feed_asset_price_at(wasm, admin, feed_addr, asset, new_price, get_start_time());
+    }
+
+    // Helper function to update the price of any asset in existing CLP feed, produced at `price_time`
+    fn update_clp_feed_asset_price_at(wasm: &Wasm<'_, CoreumTestApp>, admin: &SigningAccount, feed_addr: &str, asset: &str, new_price: &str, price_time: Timestamp) {
         let validator1_key = signing_key_from_seed("0000000000000000000000000000000000000000000000000000000000000001", "validator_1");
         let validator2_key = signing_key_from_seed("0000000000000000000000000000000000000000000000000000000000000002", "validator_2");
 
@@ -102,17 +120,16 @@ mod tests {
             feed_addr,
             &clp_feed_interface::msg::ExecuteMsg::SubmitPrice {
                 aggregated_price: AggregatedPrice {
-                    asset: "CORE".to_string(),
+                    asset: asset.to_string(),
                     price: new_price.to_string(),
-                    timestamp: get_start_time(),
+                    timestamp: price_time,
                     deviation: None,
                     submissions: vec![],
                     feed_block_height: 0,
                     chain_block_height: None,
                 },
                 validator_submissions: vec![validator1_key.clone(), validator2_key.clone()].iter_mut().map(|validator_key| {
-                    let asset = "CORE";
-                    let timestamp = get_start_time().seconds();
+                    let timestamp = price_time.seconds();
                     let sources = vec![];
 
                     let signature = validator_key.sign_price_submission(
@@ -199,6 +216,11 @@ mod tests {
                         denom: "ucore".to_string(),
                         decimals: 6,
                     },
+                    AssetInfo {
+                        name: "BTC".to_string(),
+                        denom: "ubtc".to_string(),
+                        decimals: 8,
+                    },
                 ],
             },
             Some(&admin.address()),
@@ -277,20 +299,7 @@ mod tests {
         let create_market_res = wasm
             .execute(
                 &registry_address,
-                &RegistryExecuteMsg::CreateMarket {
-                    id: "test_market_1".to_string(),
-                    start_time: get_start_time(),
-                    end_time: get_end_time(),
-                    buy_token: BUY_TOKEN.to_string(),
-                    banner_url: "https://example.com/banner.png".to_string(),
-                    description: "Test prediction market for integration testing".to_string(),
-                    title: "Test Market".to_string(),
-                    resolution_source: "https://example.com/resolution".to_string(),
-                    oracle: Addr::unchecked(feed_addr.clone()),
-                    asset_to_track: "CORE".to_string(),
-                    market_type: MarketType::PriceAt,
-                    target_price: Decimal::from_str("1.5").unwrap(), // Target price higher than initial price
-                },
+                &create_market_msg("test_market_1", &feed_addr),
                 &[coin(20_000_000, FEE_DENOM)], // Required payment for market creation
                 admin,
             )
@@ -338,6 +347,59 @@ mod tests {
         (registry_address, market_address, feed_addr)
     }
 
+    // Helper function to build the CreateMarket message used by setup_registry_and_market
+    fn create_market_msg(id: &str, feed_addr: &str) -> RegistryExecuteMsg {
+        RegistryExecuteMsg::CreateMarket {
+            id: id.to_string(),
+            start_time: get_start_time(),
+            end_time: get_end_time(),
+            buy_token: BUY_TOKEN.to_string(),
+            banner_url: "https://example.com/banner.png".to_string(),
+            description: "Test prediction market for integration testing".to_string(),
+            title: "Test Market".to_string(),
+            resolution_source: "https://example.com/resolution".to_string(),
+            oracle: Addr::unchecked(feed_addr),
+            asset_to_track: "CORE".to_string(),
+            second_asset_to_track: None,
+            market_type: MarketType::PriceAt,
+            target_price: Decimal::from_str("1.5").unwrap(),
+            resolution_timeout: None,
+            max_price_staleness: None,
+            resolution_mode: ResolutionMode::AdminOnly,
+            resolution_bounty: Uint128::zero(),
+            dispute_config: None,
+            resolvers: None,
+            oracle_quorum: None,
+            twap_window: None,
+            pricing_mode: None,
+            tax_config: None,
+        }
+    }
+
+    // Helper function to create an extra market through the registry, returns its address
+    fn create_market(
+        wasm: &Wasm<'_, CoreumTestApp>,
+        admin: &SigningAccount,
+        registry_address: &str,
+        msg: &RegistryExecuteMsg,
+    ) -> String {
+        // 10 COREUM for each outcome token the market issues
+        let RegistryExecuteMsg::CreateMarket { market_type, .. } = msg;
+        let issue_fee = 10_000_000 * market_type.get_options().len() as u128;
+        let create_market_res = wasm
+            .execute(registry_address, msg, &[coin(issue_fee, FEE_DENOM)], admin)
+            .unwrap();
+
+        create_market_res
+            .events
+            .iter()
+            .filter(|e| e.ty == "instantiate")
+            .flat_map(|e| e.attributes.iter())
+            .find(|attr| attr.key == "_contract_address")
+            .map(|attr| attr.value.clone())
+            .unwrap()
+    }
+
     #[test]
     fn test_market_creation_through_registry() {
         let app = CoreumTestApp::new();
@@ -381,7 +443,7 @@ mod tests {
 
         assert_eq!(market.id, "test_market_1");
         assert_eq!(market.options, vec!["Yes".to_string(), "No".to_string()]);
-        assert_eq!(market.status, MarketStatus::Pending);
+        assert_eq!(market.status, MarketStatus::Active);
         assert_eq!(market.buy_token, BUY_TOKEN);
         assert_eq!(market.title, "Test Market");
     }
@@ -416,6 +478,8 @@ mod tests {
                 &ExecuteMsg::BuyShare {
                     market_id: "test_market_1".to_string(),
                     option: "Yes".to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
                 },
                 &[coin(1000, BUY_TOKEN)],
                 &user1,
@@ -478,6 +542,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user1,
@@ -490,6 +556,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(2000, BUY_TOKEN)],
             &user2,
@@ -580,6 +648,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(user1_betting_amount, BUY_TOKEN)],
             &user1,
@@ -591,6 +661,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(user2_betting_amount, BUY_TOKEN)],
             &user2,
@@ -732,6 +804,183 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cancel_market_and_claim_refund() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user2 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(2000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(2000, BUY_TOKEN)],
+            &user2,
+        )
+        .unwrap();
+
+        // Only the admin can cancel the market
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::CancelMarket {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &user1,
+        );
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Unauthorized"));
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::CancelMarket {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.status, MarketStatus::Cancelled);
+
+        // The refundable amount is reported through the winnings query
+        let winnings: UserWinningsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetUserWinnings {
+                    market_id: "test_market_1".to_string(),
+                    user: Addr::unchecked(user1.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(winnings.winnings.amount, calculate_net_amount(1000).to_string());
+
+        // Buying into a cancelled market is rejected
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(100, BUY_TOKEN)],
+            &admin,
+        );
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Market has been cancelled"));
+
+        // User1 returns their outcome tokens and gets their net stake back
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ClaimRefund {
+                market_id: "test_market_1".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), &market.token_a.denom)],
+            &user1,
+        )
+        .unwrap();
+
+        let user1_balance_after = bank
+            .query_balance(&QueryBalanceRequest {
+                address: user1.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(user1_balance_after, calculate_net_amount(1000).to_string());
+
+        // Shares are consumed by the refund, a second claim has nothing left to return
+        let shares: AllSharesResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetShares {
+                    market_id: "test_market_1".to_string(),
+                    user: Addr::unchecked(user1.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(shares.shares[0].amount.amount, "0");
+    }
+
+    #[test]
+    fn test_cancel_market_rejected_after_close() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // The outcome may be known once trading has closed
+        app.increase_time(TIME_TO_END);
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::CancelMarket {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &admin,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Market is closed for trading"));
+    }
+
     #[test]
     fn test_odds_calculation() {
         let app = CoreumTestApp::new();
@@ -767,6 +1016,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(3000, BUY_TOKEN)],
             &user1,
@@ -779,6 +1030,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user2,
@@ -883,6 +1136,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(2000, BUY_TOKEN)],
             &user1,
@@ -895,6 +1150,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user2,
@@ -974,6 +1231,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user1,
@@ -984,6 +1243,57 @@ mod tests {
         assert!(error_msg.contains("Market is already resolved"));
     }
 
+    #[test]
+    fn test_buying_shares_after_market_end_fails() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        app.increase_time(TIME_TO_END);
+
+        // The market reports Closed once end_time has passed, even before resolution
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.status, MarketStatus::Closed);
+
+        // Try to buy shares after the market ended (should fail)
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        );
+
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("Market is closed for trading"));
+    }
+
     #[test]
     fn test_sell_shares_functionality() {
         let app = CoreumTestApp::new();
@@ -1014,6 +1324,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(2000, BUY_TOKEN)],
             &user1,
@@ -1064,6 +1376,8 @@ mod tests {
                 &market_address,
                 &ExecuteMsg::SellShare {
                     option: "Yes".to_string(),
+                    min_amount_out: None,
+                    max_tax_rate: None,
                 },
                 &[coin(1000, &market.token_a.denom)], // Send tokens to sell
                 &user1,
@@ -1161,6 +1475,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user1,
@@ -1182,6 +1498,8 @@ mod tests {
             &market_address,
             &ExecuteMsg::SellShare {
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                max_tax_rate: None,
             },
             &[coin(2000, &market.token_a.denom)], // Trying to sell 2000 when only has 1000
             &user1,
@@ -1232,6 +1550,8 @@ mod tests {
             &market_address,
             &ExecuteMsg::SellShare {
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                max_tax_rate: None,
             },
             &[coin(1000, &market.token_a.denom)],
             &user1,
@@ -1280,6 +1600,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(2000, BUY_TOKEN)],
             &user_a,
@@ -1382,6 +1704,8 @@ mod tests {
             &market_address,
             &ExecuteMsg::SellShare {
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                max_tax_rate: None,
             },
             &[coin(1000, &market.token_a.denom)], // User B tries to sell 1000 of the 1500 received tokens
             &user_b,
@@ -1398,6 +1722,8 @@ mod tests {
             &market_address,
             &ExecuteMsg::SellShare {
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                max_tax_rate: None,
             },
             &[coin(400, &market.token_a.denom)], // User A sells their remaining 500 tokens
             &user_a,
@@ -1477,6 +1803,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user1,
@@ -1499,6 +1827,8 @@ mod tests {
                 &market_address,
                 &ExecuteMsg::SellShare {
                     option: "Yes".to_string(),
+                    min_amount_out: None,
+                    max_tax_rate: None,
                 },
                 &[coin(200, &market.token_a.denom)], // Sell 200 tokens
                 &user1,
@@ -1612,6 +1942,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user1,
@@ -1647,6 +1979,8 @@ mod tests {
             &market_address,
             &ExecuteMsg::SellShare {
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                max_tax_rate: None,
             },
             &[coin(500, &market.token_a.denom)],
             &user1,
@@ -1834,6 +2168,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(1000, BUY_TOKEN)],
             &user1,
@@ -1846,6 +2182,8 @@ mod tests {
             &ExecuteMsg::BuyShare {
                 market_id: "test_market_1".to_string(),
                 option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
             },
             &[coin(2000, BUY_TOKEN)],
             &user2,
@@ -2026,5 +2364,3939 @@ mod tests {
         println!("✅ Correctly prevented early resolution: {}", error_msg);
     }
 
+    #[test]
+    fn test_void_market_after_resolution_deadline() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+
+        // Voiding is only possible once the resolution deadline has passed
+        app.increase_time(TIME_TO_END);
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::VoidMarket {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &user1,
+        );
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Resolution deadline not reached yet"));
+
+        app.increase_time(DEFAULT_RESOLUTION_TIMEOUT);
+
+        // Resolution is no longer possible, anyone can void the market
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &admin,
+        );
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Resolution deadline has passed"));
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::VoidMarket {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &user1,
+        )
+        .unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.status, MarketStatus::Void);
+
+        let winnings: UserWinningsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetUserWinnings {
+                    market_id: "test_market_1".to_string(),
+                    user: Addr::unchecked(user1.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(winnings.winnings.amount, calculate_net_amount(1000).to_string());
+
+        // User1 gets back their cost basis
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ClaimRefund {
+                market_id: "test_market_1".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), &market.token_a.denom)],
+            &user1,
+        )
+        .unwrap();
+
+        let user1_balance_after = bank
+            .query_balance(&QueryBalanceRequest {
+                address: user1.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(user1_balance_after, calculate_net_amount(1000).to_string());
+    }
+
+    #[test]
+    fn test_resolve_uses_price_snapshot_at_end_time() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let keeper = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (_registry_address, market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // A keeper snapshots the oracle price before end_time
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        let snapshot_res = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::SnapshotPrice {
+                    market_id: "test_market_1".to_string(),
+                },
+                &[],
+                &keeper,
+            )
+            .unwrap();
+
+        let snapshot_timestamp = snapshot_res
+            .events
+            .iter()
+            .find(|e| e.ty.contains("snapshot_price"))
+            .and_then(|e| e.attributes.iter().find(|attr| attr.key == "price_timestamp"))
+            .map(|attr| attr.value.clone())
+            .expect("Should have price_timestamp attribute");
+
+        app.increase_time(TIME_TO_END);
+
+        let resolve_res = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::Resolve {
+                    market_id: "test_market_1".to_string(),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap();
+
+        let resolve_event = resolve_res
+            .events
+            .iter()
+            .find(|e| e.ty.contains("resolve"))
+            .expect("Should have resolve event");
+
+        let price_timestamp = resolve_event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "price_timestamp")
+            .map(|attr| attr.value.clone())
+            .expect("Should have price_timestamp attribute");
+
+        assert_eq!(price_timestamp, snapshot_timestamp);
+
+        // The settlement evidence is readable from contract state
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.expect("Market should have a resolution record");
+
+        assert_eq!(resolution.winning_option.text, "Yes");
+        assert_eq!(resolution.price, Decimal::from_str("2.0").unwrap());
+        assert_eq!(resolution.price_timestamp.to_string(), snapshot_timestamp);
+        assert_eq!(resolution.oracle, Addr::unchecked(feed_addr));
+        assert_eq!(resolution.resolver, Addr::unchecked(admin.address()));
+        assert_eq!(resolution.initial_price, Decimal::from_str("1.0").unwrap());
+        assert_eq!(resolution.target_price, Decimal::from_str("1.5").unwrap());
+    }
+
+    #[test]
+    fn test_range_market_resolves_to_price_bucket() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+            *market_type = MarketType::Range {
+                boundaries: vec![
+                    Decimal::from_str("1.5").unwrap(),
+                    Decimal::from_str("2").unwrap(),
+                    Decimal::from_str("2.5").unwrap(),
+                ],
+            };
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // One outcome per bucket, including the open-ended edges
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            market.options,
+            vec!["Under 1.5", "1.5 to 2", "2 to 2.5", "2.5 or more"]
+        );
+        assert_eq!(market.options_with_odds.len(), 4);
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.1");
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(resolution.resolution.unwrap().winning_option.text, "2 to 2.5");
+    }
+
+    #[test]
+    fn test_up_down_market_resolves_against_start_price() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // The market starts right away, so the strike is the current price of 1.0
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+            *market_type = MarketType::UpDown;
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let start_res = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::StartMarket {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap();
+
+        let updated = start_res
+            .events
+            .iter()
+            .find(|e| e.ty.contains("start_market"))
+            .and_then(|e| e.attributes.iter().find(|attr| attr.key == "updated"))
+            .map(|attr| attr.value.clone())
+            .expect("Should have updated attribute");
+        assert_eq!(updated, "false");
+
+        // 1.2 is below target_price but above the strike
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.2");
+        app.increase_time(TIME_TO_END);
+
+        let resolve_res = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::Resolve {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap();
+
+        // The event reports the strike the market settled against
+        let reported_target = resolve_res
+            .events
+            .iter()
+            .find(|e| e.ty.contains("prediction_market_resolve"))
+            .and_then(|e| e.attributes.iter().find(|attr| attr.key == "target_price"))
+            .map(|attr| attr.value.clone())
+            .expect("Should have target_price attribute");
+        assert_eq!(reported_target, "1");
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let strike = resolution.strike.expect("UpDown market should have a strike");
+        let resolution = resolution.resolution.unwrap();
+
+        assert_eq!(strike.price, Decimal::from_str("1.0").unwrap());
+        assert_eq!(resolution.target_price, strike.price);
+        assert_eq!(resolution.winning_option.text, "Up");
+    }
+
+    #[test]
+    fn test_keeper_records_strike_until_start_time() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let keeper = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // The market starts in an hour
+        let start_time = app.get_block_timestamp().plus_seconds(3600);
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            market_type,
+            start_time: market_start,
+            end_time,
+            ..
+        } = &mut msg
+        {
+            *market_type = MarketType::UpDown;
+            *market_start = start_time;
+            *end_time = start_time.plus_seconds(TIME_TO_END);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let start_market = || {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::StartMarket {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &keeper,
+            )
+        };
+        let start_market_updated = || -> String {
+            start_market()
+                .unwrap()
+                .events
+                .iter()
+                .find(|e| e.ty.contains("start_market"))
+                .and_then(|e| e.attributes.iter().find(|attr| attr.key == "updated"))
+                .map(|attr| attr.value.clone())
+                .expect("Should have updated attribute")
+        };
+        let strike = || -> Decimal {
+            let resolution: ResolutionResponse = wasm
+                .query(
+                    &market_address,
+                    &QueryMsg::GetResolution {
+                        market_id: "test_market_2".to_string(),
+                    },
+                )
+                .unwrap();
+            resolution.strike.expect("Strike should be recorded").price
+        };
+
+        // Too early to take a strike
+        let result = start_market();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Market has not started yet"));
+
+        // Shortly before start_time the keeper records the latest price as the strike
+        app.increase_time(3600 - 200);
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "1.1", app.get_block_timestamp());
+        assert_eq!(start_market_updated(), "true");
+        assert_eq!(strike(), Decimal::from_str("1.1").unwrap());
+
+        // A later price before start_time is closer, the strike moves to it
+        app.increase_time(150);
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "1.2", app.get_block_timestamp());
+        assert_eq!(start_market_updated(), "true");
+        assert_eq!(strike(), Decimal::from_str("1.2").unwrap());
+
+        // From start_time on the strike is final
+        app.increase_time(100);
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "1.5", app.get_block_timestamp());
+        assert_eq!(start_market_updated(), "false");
+        assert_eq!(strike(), Decimal::from_str("1.2").unwrap());
+    }
+
+    #[test]
+    fn test_strike_far_from_start_time_is_rejected() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let start_time = app.get_block_timestamp().plus_seconds(60);
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            market_type,
+            start_time: market_start,
+            end_time,
+            ..
+        } = &mut msg
+        {
+            *market_type = MarketType::UpDown;
+            *market_start = start_time;
+            *end_time = start_time.plus_seconds(TIME_TO_END);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // Nobody recorded a strike and the first price comes long after start_time
+        app.increase_time(60 + STRIKE_PRICE_TOLERANCE + 60);
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "1.3", app.get_block_timestamp());
+
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_2".to_string(),
+                option: "Up".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &admin,
+        );
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("too far from the market start"));
+    }
+
+    #[test]
+    fn test_touch_market_resolves_early_once_barrier_is_hit() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let keeper = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+            *market_type = MarketType::Touch;
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // The price briefly crosses the 1.5 barrier and a keeper records it
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.6");
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ObservePrice {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &keeper,
+        )
+        .unwrap();
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.2");
+
+        // The outcome is known, so trading stops
+        let buy_err = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: "Yes".to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                &admin,
+            )
+            .unwrap_err();
+        assert!(buy_err.to_string().contains("Barrier was already hit"));
+
+        // And the market resolves before end_time
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+
+        assert_eq!(resolution.winning_option.text, "Yes");
+        assert_eq!(resolution.price, Decimal::from_str("1.6").unwrap());
+    }
+
+    #[test]
+    fn test_scalar_market_splits_pot_linearly() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user2 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+            *market_type = MarketType::Scalar {
+                lower_bound: Decimal::from_str("1.0").unwrap(),
+                upper_bound: Decimal::from_str("2.0").unwrap(),
+            };
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        for (user, option) in [(&user1, "Long"), (&user2, "Short")] {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: option.to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                user,
+            )
+            .unwrap();
+        }
+
+        // 1.25 is a quarter of the way between the bounds
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.25");
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // The pot of 1900 (after commission) is split 25% / 75%
+        for (user, expected) in [(&user1, "475"), (&user2, "1425")] {
+            let winnings: UserWinningsResponse = wasm
+                .query(
+                    &market_address,
+                    &QueryMsg::GetUserWinnings {
+                        market_id: "test_market_2".to_string(),
+                        user: Addr::unchecked(user.address()),
+                    },
+                )
+                .unwrap();
+            assert_eq!(winnings.winnings.amount, expected);
+        }
+    }
+
+    #[test]
+    fn test_scalar_side_without_stake_leaves_pot_to_other_side() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+            *market_type = MarketType::Scalar {
+                lower_bound: Decimal::from_str("1.0").unwrap(),
+                upper_bound: Decimal::from_str("2.0").unwrap(),
+            };
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // Nobody takes the Short side
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_2".to_string(),
+                option: "Long".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.25");
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // Long would get a quarter of the pot, the unclaimed Short part goes to Long too
+        let winnings: UserWinningsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetUserWinnings {
+                    market_id: "test_market_2".to_string(),
+                    user: Addr::unchecked(user1.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(winnings.winnings.amount, calculate_net_amount(1000).to_string());
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), &market.token_a.denom)],
+            &user1,
+        )
+        .unwrap();
+
+        let balance = bank
+            .query_balance(&QueryBalanceRequest {
+                address: user1.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(balance, calculate_net_amount(1000).to_string());
+    }
+
+    #[test]
+    fn test_scalar_withdraw_settles_only_returned_side() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(2000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+        let balance = || -> String {
+            bank.query_balance(&QueryBalanceRequest {
+                address: user1.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+        };
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+            *market_type = MarketType::Scalar {
+                lower_bound: Decimal::from_str("1.0").unwrap(),
+                upper_bound: Decimal::from_str("2.0").unwrap(),
+            };
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // The same user holds both sides
+        for option in ["Long", "Short"] {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: option.to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                &user1,
+            )
+            .unwrap();
+        }
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.25");
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let withdraw = |denom: &str| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::Withdraw {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[coin(calculate_net_amount(1000), denom)],
+                &user1,
+            )
+            .unwrap();
+        };
+
+        // Returning the Short tokens only pays the Short part of the 1900 pot
+        withdraw(&market.token_b.denom);
+        assert_eq!(balance(), "1425");
+
+        withdraw(&market.token_a.denom);
+        assert_eq!(balance(), "1900");
+    }
+
+    #[test]
+    fn test_relative_market_resolves_on_percentage_gain() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+        update_clp_feed_asset_price(&wasm, &admin, &feed_addr, "BTC", "100");
+
+        // CORE vs BTC, both struck right away at 1.0 and 100
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            market_type,
+            second_asset_to_track,
+            ..
+        } = &mut msg
+        {
+            *market_type = MarketType::Relative;
+            *second_asset_to_track = Some("BTC".to_string());
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // CORE gains 10% while BTC gains 5%
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.1");
+        update_clp_feed_asset_price(&wasm, &admin, &feed_addr, "BTC", "105");
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            resolution.second_strike.unwrap().price,
+            Decimal::from_str("100").unwrap()
+        );
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.winning_option.text, "Outperform");
+        assert_eq!(resolution.second_price, Some(Decimal::from_str("105").unwrap()));
+    }
+
+    #[test]
+    fn test_event_market_resolved_by_resolver_votes() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let resolver = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            market_type,
+            resolvers,
+            ..
+        } = &mut msg
+        {
+            *market_type = MarketType::Event {
+                options: vec!["Home".to_string(), "Away".to_string(), "Draw".to_string()],
+            };
+            *resolvers = Some(ResolverSet {
+                members: vec![
+                    Addr::unchecked(admin.address()),
+                    Addr::unchecked(resolver.address()),
+                ],
+                threshold: 2,
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        app.increase_time(TIME_TO_END);
+
+        // Committee markets never settle through Resolve
+        let resolve_err = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::Resolve {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap_err();
+        assert!(resolve_err
+            .to_string()
+            .contains("resolved by their members' votes"));
+
+        // One vote is not enough for a 2-of-2 resolver set
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::SubmitOutcome {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Draw".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.status, MarketStatus::Closed);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::SubmitOutcome {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Draw".to_string(),
+            },
+            &[],
+            &resolver,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+
+        assert_eq!(resolution.winning_option.text, "Draw");
+        assert_eq!(resolution.resolver, Addr::unchecked(resolver.address()));
+    }
+
+    #[test]
+    fn test_event_option_labels_must_fit_token_names() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let cases = [
+            (vec!["Yes!", "No"], "can't be used in a token subunit or symbol"),
+            (vec!["Équipe A", "Équipe B"], "can't be used in a token subunit or symbol"),
+            (
+                vec!["A label far too long to fit in a token subunit", "No"],
+                "can't be used in a token subunit or symbol",
+            ),
+            // Both labels would issue the same token
+            (vec!["Team A", "team a"], "is listed more than once"),
+        ];
+        for (options, expected) in cases {
+            let mut msg = create_market_msg("test_market_2", &feed_addr);
+            if let RegistryExecuteMsg::CreateMarket { market_type, .. } = &mut msg {
+                *market_type = MarketType::Event {
+                    options: options.iter().map(|option| option.to_string()).collect(),
+                };
+            }
+            let result = wasm.execute(
+                &registry_address,
+                &msg,
+                &[coin(20_000_000, FEE_DENOM)],
+                &admin,
+            );
+            assert!(result.is_err());
+            assert!(result.unwrap_err().to_string().contains(expected));
+        }
+    }
+
+    #[test]
+    fn test_resolver_committee_resolves_oracle_market_on_threshold() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let members = app
+            .init_accounts(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)], 3)
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { resolvers, .. } = &mut msg {
+            *resolvers = Some(ResolverSet {
+                members: members
+                    .iter()
+                    .map(|member| Addr::unchecked(member.address()))
+                    .collect(),
+                threshold: 2,
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+
+        // Members vote on the outcome instead of taking it from the oracle
+        let resolve_err = wasm
+            .execute(
+                &market_address,
+                &ExecuteMsg::Resolve {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &members[0],
+            )
+            .unwrap_err();
+        assert!(resolve_err
+            .to_string()
+            .contains("resolved by their members' votes"));
+
+        let submit_msg = ExecuteMsg::SubmitOutcome {
+            market_id: "test_market_2".to_string(),
+            winning_option: "Yes".to_string(),
+        };
+
+        // The admin is not part of the committee
+        let admin_err = wasm
+            .execute(&market_address, &submit_msg, &[], &admin)
+            .unwrap_err();
+        assert!(admin_err.to_string().contains("Only the market resolvers"));
+
+        // The first vote is recorded without resolving the market
+        wasm.execute(&market_address, &submit_msg, &[], &members[0])
+            .unwrap();
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(resolution.resolution.is_none());
+        assert_eq!(resolution.votes.len(), 1);
+
+        // The second matching vote reaches the threshold and ends the round
+        wasm.execute(&market_address, &submit_msg, &[], &members[1])
+            .unwrap();
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(resolution.votes.is_empty());
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.winning_option.text, "Yes");
+        assert_eq!(resolution.price, Decimal::from_str("2.0").unwrap());
+    }
+
+    #[test]
+    fn test_conflicting_resolver_votes_dispute_the_market() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let members = app
+            .init_accounts(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)], 3)
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            market_type,
+            resolvers,
+            dispute_config,
+            ..
+        } = &mut msg
+        {
+            *market_type = MarketType::Event {
+                options: vec!["Passed".to_string(), "Rejected".to_string()],
+            };
+            *resolvers = Some(ResolverSet {
+                members: vec![
+                    Addr::unchecked(members[0].address()),
+                    Addr::unchecked(members[1].address()),
+                ],
+                threshold: 2,
+            });
+            *dispute_config = Some(DisputeConfig {
+                arbitrator: Addr::unchecked(members[2].address()),
+                challenge_period: 3600,
+                bond: Uint128::from(500u128),
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        app.increase_time(TIME_TO_END);
+
+        for (member, option) in [(&members[0], "Passed"), (&members[1], "Rejected")] {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SubmitOutcome {
+                    market_id: "test_market_2".to_string(),
+                    winning_option: option.to_string(),
+                },
+                &[],
+                member,
+            )
+            .unwrap();
+        }
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(matches!(market.status, MarketStatus::Disputed(_)));
+
+        // The arbitrator rules on the conflict
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ArbitrateDispute {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Rejected".to_string(),
+            },
+            &[],
+            &members[2],
+        )
+        .unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        if let MarketStatus::Resolved(winning_option) = market.status {
+            assert_eq!(winning_option.text, "Rejected");
+        } else {
+            panic!("Market should be resolved");
+        }
+    }
+
+    #[test]
+    fn test_committee_disputed_scalar_market_settles_on_price() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let users = app
+            .init_accounts(
+                &[
+                    coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                    coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+                ],
+                2,
+            )
+            .unwrap();
+        let members = app
+            .init_accounts(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)], 3)
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let start = app.get_block_timestamp();
+        let end = start.plus_seconds(TIME_TO_END);
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            start_time,
+            end_time,
+            market_type,
+            resolvers,
+            dispute_config,
+            ..
+        } = &mut msg
+        {
+            *start_time = start;
+            *end_time = end;
+            *market_type = MarketType::Scalar {
+                lower_bound: Decimal::from_str("1.0").unwrap(),
+                upper_bound: Decimal::from_str("2.0").unwrap(),
+            };
+            *resolvers = Some(ResolverSet {
+                members: vec![
+                    Addr::unchecked(members[0].address()),
+                    Addr::unchecked(members[1].address()),
+                ],
+                threshold: 2,
+            });
+            *dispute_config = Some(DisputeConfig {
+                arbitrator: Addr::unchecked(members[2].address()),
+                challenge_period: 3600,
+                bond: Uint128::from(500u128),
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // Uneven stakes, so the split of the pot has to round
+        for (user, option, amount) in [(&users[0], "Long", 1001), (&users[1], "Short", 333)] {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: option.to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(amount, BUY_TOKEN)],
+                user,
+            )
+            .unwrap();
+        }
+
+        update_clp_feed_asset_price_at(
+            &wasm,
+            &admin,
+            &feed_addr,
+            "CORE",
+            "1.25",
+            end.minus_seconds(100),
+        );
+        app.increase_time(TIME_TO_END);
+
+        let submit = |member: &SigningAccount, option: &str| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SubmitOutcome {
+                    market_id: "test_market_2".to_string(),
+                    winning_option: option.to_string(),
+                },
+                &[],
+                member,
+            )
+            .unwrap()
+        };
+
+        // The members read different closing prices, 1.25 favours Short and 1.75 Long
+        submit(&members[0], "Short");
+        update_clp_feed_asset_price_at(
+            &wasm,
+            &admin,
+            &feed_addr,
+            "CORE",
+            "1.75",
+            end.minus_seconds(50),
+        );
+        submit(&members[1], "Long");
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(matches!(market.status, MarketStatus::Disputed(_)));
+
+        // The deadlocked round is over, its votes are not kept
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(resolution.votes.is_empty());
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ArbitrateDispute {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Long".to_string(),
+            },
+            &[],
+            &members[2],
+        )
+        .unwrap();
+
+        // The ruling keeps the settlement price and pays out along it
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.winning_option.text, "Long");
+        assert_eq!(resolution.price, Decimal::from_str("1.75").unwrap());
+        assert_eq!(resolution.price_timestamp, end.minus_seconds(50));
+        assert_eq!(resolution.scalar_payout, Some(Decimal::percent(75)));
+
+        // Each side's share is rounded down, so the payouts never exceed the pot
+        let pot = Uint128::from(calculate_net_amount(1001) + calculate_net_amount(333));
+        let long_payout = pot.mul_floor(Decimal::percent(75));
+        let short_payout = pot.mul_floor(Decimal::percent(25));
+        assert!(long_payout + short_payout <= pot);
+        for (user, expected) in [(&users[0], long_payout), (&users[1], short_payout)] {
+            let winnings: UserWinningsResponse = wasm
+                .query(
+                    &market_address,
+                    &QueryMsg::GetUserWinnings {
+                        market_id: "test_market_2".to_string(),
+                        user: Addr::unchecked(user.address()),
+                    },
+                )
+                .unwrap();
+            assert_eq!(winnings.winnings.amount, expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_split_committee_waits_for_remaining_votes() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let members = app
+            .init_accounts(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)], 3)
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let committee_msg = |committee: &[&SigningAccount], threshold: u32| {
+            let mut msg = create_market_msg("test_market_2", &feed_addr);
+            if let RegistryExecuteMsg::CreateMarket {
+                market_type,
+                resolvers,
+                dispute_config,
+                ..
+            } = &mut msg
+            {
+                *market_type = MarketType::Event {
+                    options: vec!["Passed".to_string(), "Rejected".to_string()],
+                };
+                *resolvers = Some(ResolverSet {
+                    members: committee
+                        .iter()
+                        .map(|member| Addr::unchecked(member.address()))
+                        .collect(),
+                    threshold,
+                });
+                *dispute_config = Some(DisputeConfig {
+                    arbitrator: Addr::unchecked(admin.address()),
+                    challenge_period: 3600,
+                    bond: Uint128::from(500u128),
+                });
+            }
+            msg
+        };
+
+        // A committee needs between 1 and all of its distinct members to agree
+        for (committee, threshold) in [
+            ([&members[0], &members[1]], 0),
+            ([&members[0], &members[1]], 3),
+            ([&members[0], &members[0]], 2),
+        ] {
+            let result = wasm.execute(
+                &registry_address,
+                &committee_msg(&committee, threshold),
+                &[coin(20_000_000, FEE_DENOM)],
+                &admin,
+            );
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .contains("Resolver threshold must be between 1"));
+        }
+
+        let market_address = create_market(
+            &wasm,
+            &admin,
+            &registry_address,
+            &committee_msg(&[&members[0], &members[1], &members[2]], 2),
+        );
+
+        let submit = |member: &SigningAccount, option: &str| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SubmitOutcome {
+                    market_id: "test_market_2".to_string(),
+                    winning_option: option.to_string(),
+                },
+                &[],
+                member,
+            )
+        };
+
+        // Nobody votes before the event is decided
+        let early_err = submit(&members[0], "Passed").unwrap_err();
+        assert!(early_err.to_string().contains("Market has not ended yet"));
+
+        app.increase_time(TIME_TO_END);
+
+        // Only members vote, and only for one of the options
+        let outsider_err = submit(&admin, "Passed").unwrap_err();
+        assert!(outsider_err
+            .to_string()
+            .contains("Only the market resolvers"));
+        let option_err = submit(&members[0], "Postponed").unwrap_err();
+        assert!(option_err.to_string().contains("Invalid option"));
+
+        // One vote each way, the third member can still settle it
+        submit(&members[0], "Passed").unwrap();
+        submit(&members[1], "Rejected").unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(resolution.resolution.is_none());
+        assert_eq!(resolution.votes.len(), 2);
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.status, MarketStatus::Closed);
+
+        submit(&members[2], "Rejected").unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            resolution.resolution.unwrap().winning_option.text,
+            "Rejected"
+        );
+    }
+
+    #[test]
+    fn test_oracle_quorum_requires_enough_feeds() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // The registry does not answer price queries, so only one of two feeds responds
+        let mut quorum = OracleQuorum {
+            oracles: vec![
+                Addr::unchecked(feed_addr.clone()),
+                Addr::unchecked(registry_address.clone()),
+            ],
+            min_responses: 2,
+            max_deviation: Decimal::percent(2),
+        };
+        for (id, min_responses) in [("test_market_2", 2), ("test_market_3", 1)] {
+            quorum.min_responses = min_responses;
+            let mut msg = create_market_msg(id, &feed_addr);
+            if let RegistryExecuteMsg::CreateMarket { oracle_quorum, .. } = &mut msg {
+                *oracle_quorum = Some(quorum.clone());
+            }
+            create_market(&wasm, &admin, &registry_address, &msg);
+        }
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+
+        let market_address = |id: &str| -> String {
+            let market_info: MarketInfo = wasm
+                .query(
+                    &registry_address,
+                    &RegistryQueryMsg::Market {
+                        market_id: id.to_string(),
+                    },
+                )
+                .unwrap();
+            market_info.contract_address.to_string()
+        };
+
+        let quorum_err = wasm
+            .execute(
+                &market_address("test_market_2"),
+                &ExecuteMsg::Resolve {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap_err();
+        assert!(quorum_err.to_string().contains("1 of 2 required oracles responded"));
+
+        // A single agreeing feed is enough for a quorum of one
+        wasm.execute(
+            &market_address("test_market_3"),
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_3".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_oracle_quorum_settles_on_keeper_snapshots() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let start = app.get_block_timestamp();
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            start_time,
+            end_time,
+            oracle_quorum,
+            ..
+        } = &mut msg
+        {
+            *start_time = start;
+            *end_time = start.plus_seconds(TIME_TO_END);
+            *oracle_quorum = Some(OracleQuorum {
+                oracles: vec![
+                    Addr::unchecked(feed_addr.clone()),
+                    Addr::unchecked(registry_address.clone()),
+                ],
+                min_responses: 1,
+                max_deviation: Decimal::percent(2),
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "2.0", start);
+
+        // A keeper snapshots every feed of the quorum before the end
+        app.increase_time(TIME_TO_END - 60);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::SnapshotPrice {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // The feed moves on after end_time, its latest price can't settle the market
+        app.increase_time(120);
+        update_clp_feed_asset_price_at(
+            &wasm,
+            &admin,
+            &feed_addr,
+            "CORE",
+            "1.0",
+            app.get_block_timestamp(),
+        );
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.winning_option.text, "Yes");
+        assert_eq!(resolution.price, Decimal::from_str("2.0").unwrap());
+        assert_eq!(resolution.price_timestamp, start);
+    }
+
+    #[test]
+    fn test_fixed_price_market_resolves_on_manual_price() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            resolution_source, ..
+        } = &mut msg
+        {
+            *resolution_source = "fixed:1.0".to_string();
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let set_price = |asset: &str, price: &str, sender: &SigningAccount| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SetManualPrice {
+                    market_id: "test_market_2".to_string(),
+                    asset: asset.to_string(),
+                    price: Decimal::from_str(price).unwrap(),
+                },
+                &[],
+                sender,
+            )
+        };
+
+        // Only the admin posts prices, and only for the tracked asset
+        assert!(set_price("CORE", "2.0", &user).is_err());
+        assert!(set_price("BTC", "2.0", &admin).is_err());
+
+        // The feed price is ignored, the posted price settles the market
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "1.0");
+        app.increase_time(TIME_TO_END - 60);
+        set_price("CORE", "2.0", &admin).unwrap();
+        let posted_at = app.get_block_timestamp();
+        app.increase_time(60);
+
+        // Prices posted after close are rejected
+        let late_err = set_price("CORE", "0.5", &admin).unwrap_err();
+        assert!(late_err
+            .to_string()
+            .contains("Oracle price is after the market end"));
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.price, Decimal::from_str("2.0").unwrap());
+        assert_eq!(resolution.price_timestamp, posted_at);
+        assert_eq!(resolution.winning_option.text, "Yes");
+    }
+
+    #[test]
+    fn test_twap_market_settles_on_closing_window_average() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let keeper = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // Settle on the TWAP of the last 3 hours
+        let twap_window_secs = 3 * 3600;
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { twap_window, .. } = &mut msg {
+            *twap_window = Some(twap_window_secs);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let snapshot = || {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SnapshotPrice {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &keeper,
+            )
+            .unwrap();
+        };
+
+        // 1.0 for most of the window, then a 3.0 spike in the last 10 minutes
+        app.increase_time(TIME_TO_END - twap_window_secs + 60);
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "1.0", app.get_block_timestamp());
+        snapshot();
+
+        app.increase_time(twap_window_secs - 60 - 600);
+        update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", "3.0", app.get_block_timestamp());
+        snapshot();
+
+        app.increase_time(700);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let twap: TwapObservationsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetTwapObservations {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(twap.observations.len(), 2);
+        assert!(twap.twap.unwrap() < Decimal::from_str("1.5").unwrap());
+
+        // The spike alone would have settled above the 1.5 target
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.price, twap.twap.unwrap());
+        assert_eq!(resolution.winning_option.text, "No");
+    }
+
+    #[test]
+    fn test_twap_weighs_opening_price_and_keeps_latest_per_bucket() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        // A one hour window, split in buckets of 36 seconds
+        let twap_window_secs = 3600;
+        let start = app.get_block_timestamp();
+        let end = start.plus_seconds(TIME_TO_END);
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            start_time,
+            end_time,
+            twap_window,
+            ..
+        } = &mut msg
+        {
+            *start_time = start;
+            *end_time = end;
+            *twap_window = Some(twap_window_secs);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let observe = |price: &str, price_time: Timestamp| {
+            update_clp_feed_asset_price_at(&wasm, &admin, &feed_addr, "CORE", price, price_time);
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SnapshotPrice {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap();
+        };
+
+        // The price before the window opens it
+        app.increase_time(TIME_TO_END - twap_window_secs - 600);
+        observe("1.0", app.get_block_timestamp());
+
+        // Two late observations in the same bucket, the later one replaces the earlier one
+        app.increase_time(600 + twap_window_secs - 300);
+        observe("3.0", end.minus_seconds(300));
+        app.increase_time(5);
+        observe("3.2", end.minus_seconds(295));
+
+        let twap: TwapObservationsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetTwapObservations {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let observed: Vec<String> = twap
+            .observations
+            .iter()
+            .map(|observation| observation.price.to_string())
+            .collect();
+        assert_eq!(observed, vec!["1", "3.2"]);
+
+        // 1.0 holds for 3305 seconds of the window and 3.2 for the last 295:
+        // (3305 * 1.0 + 295 * 3.2) / 3600 = 1.180277...
+        let expected = (Decimal::from_ratio(3305u128, 1u128)
+            + Decimal::from_str("3.2").unwrap() * Decimal::from_ratio(295u128, 1u128))
+            / Decimal::from_ratio(3600u128, 1u128);
+        assert_eq!(twap.twap, Some(expected));
+
+        app.increase_time(600);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // The late spike alone would have settled above the 1.5 target
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let resolution = resolution.resolution.unwrap();
+        assert_eq!(resolution.price, expected);
+        assert_eq!(resolution.winning_option.text, "No");
+    }
+
+    #[test]
+    fn test_complete_sets_mint_and_redeem_one_to_one() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let maker = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let (yes_denom, no_denom) = (market.token_a.denom, market.token_b.denom);
+
+        let buy_token_balance = || -> u128 {
+            bank.query_balance(&QueryBalanceRequest {
+                address: maker.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+            .parse()
+            .unwrap()
+        };
+        let redeem = |funds: &[cosmwasm_std::Coin]| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::RedeemCompleteSet {
+                    market_id: "test_market_1".to_string(),
+                },
+                funds,
+                &maker,
+            )
+        };
+
+        // No commission is taken on complete sets
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::MintCompleteSet {
+                market_id: "test_market_1".to_string(),
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &maker,
+        )
+        .unwrap();
+        assert_eq!(buy_token_balance(), 0);
+
+        // Before resolution only full sets are redeemed
+        assert!(redeem(&[coin(400, &yes_denom)]).is_err());
+        redeem(&[coin(400, &no_denom), coin(400, &yes_denom)]).unwrap();
+        assert_eq!(buy_token_balance(), 400);
+
+        let stats: MarketStatsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarketStats {
+                    market_id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(stats.complete_sets, Uint128::new(600));
+
+        // "Yes" wins, its tokens alone pay 1:1
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        assert!(redeem(&[coin(600, &no_denom)]).is_err());
+        redeem(&[coin(600, &yes_denom)]).unwrap();
+        assert_eq!(buy_token_balance(), 1000);
+    }
+
+    #[test]
+    fn test_complete_sets_redeem_for_any_holder_and_withdraw_pro_rata() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let buyer = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(2000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let maker = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let holder = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+        let balance = || -> String {
+            bank.query_balance(&QueryBalanceRequest {
+                address: buyer.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+        };
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let (yes_denom, no_denom) = (market.token_a.denom, market.token_b.denom);
+
+        for option in ["Yes", "No"] {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_1".to_string(),
+                    option: option.to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                &buyer,
+            )
+            .unwrap();
+        }
+
+        // Holding both sides of the pot is not backed by any complete set
+        let net = calculate_net_amount(1000);
+        let redeem = |amount: u128, sender: &SigningAccount| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::RedeemCompleteSet {
+                    market_id: "test_market_1".to_string(),
+                },
+                &[coin(amount, &no_denom), coin(amount, &yes_denom)],
+                sender,
+            )
+        };
+        assert!(redeem(net, &buyer)
+            .unwrap_err()
+            .to_string()
+            .contains("Only 0 complete sets are outstanding"));
+
+        // The set collateral is fungible, whoever holds the tokens redeems it
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::MintCompleteSet {
+                market_id: "test_market_1".to_string(),
+            },
+            &[coin(100, BUY_TOKEN)],
+            &maker,
+        )
+        .unwrap();
+        bank.send(
+            MsgSend {
+                from_address: maker.address().to_string(),
+                to_address: holder.address().to_string(),
+                amount: vec![
+                    coreum_wasm_sdk::types::cosmos::base::v1beta1::Coin {
+                        denom: yes_denom.clone(),
+                        amount: "100".to_string(),
+                    },
+                    coreum_wasm_sdk::types::cosmos::base::v1beta1::Coin {
+                        denom: no_denom.clone(),
+                        amount: "100".to_string(),
+                    },
+                ],
+            },
+            &maker,
+        )
+        .unwrap();
+        assert!(redeem(101, &holder).is_err());
+        redeem(100, &holder).unwrap();
+        let holder_balance = bank
+            .query_balance(&QueryBalanceRequest {
+                address: holder.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(holder_balance, "100");
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let withdraw = |amount: u128| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::Withdraw {
+                    market_id: "test_market_1".to_string(),
+                },
+                &[coin(amount, &yes_denom)],
+                &buyer,
+            )
+        };
+
+        // Half of the "Yes" tokens take half of the 1900 pot
+        withdraw(net / 2).unwrap();
+        assert_eq!(balance(), "950");
+
+        assert!(withdraw(net / 2 + 1).is_err());
+        withdraw(net / 2).unwrap();
+        assert_eq!(balance(), "1900");
+        assert!(withdraw(1).is_err());
+    }
+
+    #[test]
+    fn test_lmsr_market_moves_price_and_pays_one_per_token() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let trader = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { pricing_mode, .. } = &mut msg {
+            *pricing_mode = Some(PricingMode::Lmsr {
+                liquidity: Uint128::new(1000),
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let balance = |address: &str, denom: &str| -> u128 {
+            bank.query_balance(&QueryBalanceRequest {
+                address: address.to_string(),
+                denom: denom.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+            .parse()
+            .unwrap()
+        };
+        let buy_yes = || {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: "Yes".to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                &trader,
+            )
+        };
+
+        // Trading waits for the b * ln(2) subsidy
+        assert!(buy_yes().is_err());
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::FundLiquidity {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(694, BUY_TOKEN)],
+            &admin,
+        )
+        .unwrap();
+
+        // 950 after commission buys about 1428 "Yes" tokens and moves its price up
+        buy_yes().unwrap();
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        let yes_denom = market.token_a.denom;
+        let tokens = balance(&trader.address(), &yes_denom);
+        assert_eq!(tokens, 1428);
+
+        let prices: PricesResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetPrices {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(prices.prices[0].option, "Yes");
+        assert!(prices.prices[0].price > Decimal::percent(75));
+        assert_eq!(
+            prices.prices[0].price + prices.prices[1].price,
+            Decimal::one()
+        );
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // Every winning token pays one buy_token
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(tokens, &yes_denom)],
+            &trader,
+        )
+        .unwrap();
+        assert_eq!(balance(&trader.address(), BUY_TOKEN), tokens);
+
+        // The admin gets back what is left: 694 + 950 - 1428
+        let admin_before = balance(&admin.address(), BUY_TOKEN);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::WithdrawLiquidity {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+        assert_eq!(balance(&admin.address(), BUY_TOKEN), admin_before + 216);
+    }
+
+    #[test]
+    fn test_amm_pool_swaps_and_pays_lp_fees() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let provider = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(2500u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let trader = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let yes_denom = market.token_a.denom;
+
+        let balance = |address: &str, denom: &str| -> u128 {
+            bank.query_balance(&QueryBalanceRequest {
+                address: address.to_string(),
+                denom: denom.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+            .parse()
+            .unwrap()
+        };
+        let swap = |funds: &[cosmwasm_std::Coin], min_amount_out: Option<u128>| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::Swap {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    min_amount_out: min_amount_out.map(Uint128::new),
+                },
+                funds,
+                &trader,
+            )
+        };
+
+        // 1900 "Yes" tokens after commission, 1000 of them go in the pool at 0.5
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(2000, BUY_TOKEN)],
+            &provider,
+        )
+        .unwrap();
+        assert!(swap(&[coin(100, BUY_TOKEN)], None).is_err());
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ProvideLiquidity {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+            },
+            &[coin(1000, &yes_denom), coin(500, BUY_TOKEN)],
+            &provider,
+        )
+        .unwrap();
+
+        // 95 after the 5% fee buys 1000 * 95 / (500 + 95) tokens
+        assert!(swap(&[coin(100, BUY_TOKEN)], Some(160)).is_err());
+        swap(&[coin(100, BUY_TOKEN)], Some(159)).unwrap();
+        assert_eq!(balance(&trader.address(), &yes_denom), 159);
+
+        let pool: PoolResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetPool {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(pool.token_reserve, Uint128::new(841));
+        assert_eq!(pool.buy_token_reserve, Uint128::new(600));
+
+        // Exiting before end_time at the pool price, without the time tax: 600 * 152 / (841 + 152)
+        swap(&[coin(159, &yes_denom)], None).unwrap();
+        assert_eq!(balance(&trader.address(), BUY_TOKEN), 91);
+
+        // The provider takes back the pool with the fees
+        let position: LpPositionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetLpPosition {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    user: Addr::unchecked(provider.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(position.lp_shares, Uint128::new(500));
+        assert_eq!(position.token_amount, Uint128::new(1000));
+        assert_eq!(position.buy_token_amount, Uint128::new(509));
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::RemoveLiquidity {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                lp_shares: Uint128::new(500),
+            },
+            &[],
+            &provider,
+        )
+        .unwrap();
+        assert_eq!(balance(&provider.address(), &yes_denom), 1900);
+        assert_eq!(balance(&provider.address(), BUY_TOKEN), 509);
+    }
+
+    #[test]
+    fn test_pool_tokens_removed_after_withdrawing_are_paid() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let provider = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(2500u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+        let balance = || -> String {
+            bank.query_balance(&QueryBalanceRequest {
+                address: provider.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+        };
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let yes_denom = market.token_a.denom;
+
+        // 1900 "Yes" tokens, 1000 of them in the pool
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(2000, BUY_TOKEN)],
+            &provider,
+        )
+        .unwrap();
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ProvideLiquidity {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+            },
+            &[coin(1000, &yes_denom), coin(500, BUY_TOKEN)],
+            &provider,
+        )
+        .unwrap();
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_1".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let withdraw = |amount: u128| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::Withdraw {
+                    market_id: "test_market_1".to_string(),
+                },
+                &[coin(amount, &yes_denom)],
+                &provider,
+            )
+            .unwrap();
+        };
+
+        // The tokens held are withdrawn in full first
+        withdraw(900);
+        assert_eq!(balance(), "900");
+
+        // The pool tokens taken back afterwards are paid too
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::RemoveLiquidity {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                lp_shares: Uint128::new(500),
+            },
+            &[],
+            &provider,
+        )
+        .unwrap();
+        assert_eq!(balance(), "1400");
+
+        withdraw(1000);
+        assert_eq!(balance(), "2400");
+    }
+
+    #[test]
+    fn test_order_book_partial_fill_and_cancel() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let seller = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let buyer = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(420u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let yes_denom = market.token_a.denom;
+
+        let balance = |address: &str, denom: &str| -> u128 {
+            bank.query_balance(&QueryBalanceRequest {
+                address: address.to_string(),
+                denom: denom.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+            .parse()
+            .unwrap()
+        };
+        let place_order = |side: OrderSide,
+                           price: &str,
+                           amount: u128,
+                           funds: &[cosmwasm_std::Coin],
+                           account: &SigningAccount| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::PlaceOrder {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    side,
+                    price: Decimal::from_str(price).unwrap(),
+                    amount: Uint128::new(amount),
+                },
+                funds,
+                account,
+            )
+        };
+        let order_book = || -> OrderBookResponse {
+            wasm.query(
+                &market_address,
+                &QueryMsg::GetOrderBook {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    limit: None,
+                },
+            )
+            .unwrap()
+        };
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &seller,
+        )
+        .unwrap();
+        place_order(OrderSide::Ask, "0.6", 500, &[coin(500, &yes_denom)], &seller).unwrap();
+        place_order(OrderSide::Ask, "0.8", 300, &[coin(300, &yes_denom)], &seller).unwrap();
+
+        // 600 at 0.7 takes the 500 offered at 0.6, the other 100 stay open
+        place_order(OrderSide::Bid, "0.7", 600, &[coin(420, BUY_TOKEN)], &buyer).unwrap();
+        assert_eq!(balance(&buyer.address(), &yes_denom), 500);
+        assert_eq!(balance(&seller.address(), BUY_TOKEN), 300);
+
+        let book = order_book();
+        assert_eq!(
+            book.bids,
+            vec![PriceLevel {
+                price: Decimal::percent(70),
+                amount: Uint128::new(100),
+            }]
+        );
+        assert_eq!(
+            book.asks,
+            vec![PriceLevel {
+                price: Decimal::percent(80),
+                amount: Uint128::new(300),
+            }]
+        );
+
+        let open_orders: OpenOrdersResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetOpenOrders {
+                    market_id: "test_market_1".to_string(),
+                    user: Addr::unchecked(buyer.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(open_orders.orders.len(), 1);
+        assert_eq!(open_orders.orders[0].escrow, Uint128::new(120));
+
+        // Cancelling returns the unspent escrow
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::CancelOrder {
+                market_id: "test_market_1".to_string(),
+                order_id: open_orders.orders[0].id,
+            },
+            &[],
+            &buyer,
+        )
+        .unwrap();
+        assert_eq!(balance(&buyer.address(), BUY_TOKEN), 120);
+        assert!(order_book().bids.is_empty());
+
+        // No matching once the market has closed
+        app.increase_time(TIME_TO_END);
+        assert!(place_order(OrderSide::Bid, "0.9", 100, &[coin(90, BUY_TOKEN)], &buyer).is_err());
+    }
+
+    #[test]
+    fn test_order_book_small_fills_are_rounded_up() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let seller = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let buyer = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(3u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let yes_denom = market.token_a.denom;
+
+        let balance = |address: &str, denom: &str| -> u128 {
+            bank.query_balance(&QueryBalanceRequest {
+                address: address.to_string(),
+                denom: denom.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+            .parse()
+            .unwrap()
+        };
+        let place_order = |side: OrderSide,
+                           price: &str,
+                           amount: u128,
+                           funds: &[cosmwasm_std::Coin],
+                           account: &SigningAccount| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::PlaceOrder {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    side,
+                    price: Decimal::from_str(price).unwrap(),
+                    amount: Uint128::new(amount),
+                },
+                funds,
+                account,
+            )
+        };
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &seller,
+        )
+        .unwrap();
+        let seller_before = balance(&seller.address(), BUY_TOKEN);
+        place_order(OrderSide::Ask, "0.4", 10, &[coin(10, &yes_denom)], &seller).unwrap();
+
+        // A single token at 0.4 costs one buy_token, not zero
+        for _ in 0..3 {
+            place_order(OrderSide::Bid, "0.5", 1, &[coin(1, BUY_TOKEN)], &buyer).unwrap();
+        }
+        assert_eq!(balance(&buyer.address(), &yes_denom), 3);
+        assert_eq!(balance(&buyer.address(), BUY_TOKEN), 0);
+        assert_eq!(balance(&seller.address(), BUY_TOKEN), seller_before + 3);
+
+        // 3 tokens at 0.3 escrow one buy_token, which the first rounded up fill spends
+        place_order(OrderSide::Bid, "0.3", 3, &[coin(1, BUY_TOKEN)], &admin).unwrap();
+        place_order(OrderSide::Ask, "0.3", 1, &[coin(1, &yes_denom)], &seller).unwrap();
+        assert_eq!(balance(&seller.address(), BUY_TOKEN), seller_before + 4);
+
+        // The spent bid leaves the book instead of blocking the asks behind it
+        place_order(OrderSide::Ask, "0.3", 1, &[coin(1, &yes_denom)], &seller).unwrap();
+        let book: OrderBookResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetOrderBook {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert!(book.bids.is_empty());
+        assert_eq!(book.asks[0].price, Decimal::from_str("0.3").unwrap());
+        assert_eq!(book.asks[0].amount, Uint128::new(1));
+        assert_eq!(balance(&seller.address(), BUY_TOKEN), seller_before + 4);
+    }
+
+    #[test]
+    fn test_slippage_guards_on_buy_and_sell() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user2 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (_registry_address, market_address, _feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let buy_yes = |min_amount_out: Option<u128>, min_odds: Option<Decimal>| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_1".to_string(),
+                    option: "Yes".to_string(),
+                    min_amount_out: min_amount_out.map(Uint128::new),
+                    min_odds,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                &user1,
+            )
+        };
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_1".to_string(),
+                option: "No".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(2000, BUY_TOKEN)],
+            &user2,
+        )
+        .unwrap();
+
+        // 950 "Yes" tokens against 1900 on "No" pay odds of 2
+        let err = buy_yes(Some(951), None).unwrap_err().to_string();
+        assert!(err.contains("below the minimum"));
+        let err = buy_yes(None, Some(Decimal::percent(201))).unwrap_err().to_string();
+        assert!(err.contains("Odds"));
+        buy_yes(Some(950), Some(Decimal::percent(200))).unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_1".to_string(),
+                },
+            )
+            .unwrap();
+        let sell_yes = |min_amount_out: Option<u128>, max_tax_rate: Option<Decimal>| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SellShare {
+                    option: "Yes".to_string(),
+                    min_amount_out: min_amount_out.map(Uint128::new),
+                    max_tax_rate,
+                },
+                &[coin(950, &market.token_a.denom)],
+                &user1,
+            )
+        };
+
+        // Halfway through the market the time tax is well above 10%
+        app.increase_time(TIME_TO_END / 2);
+        let tax_rate: TaxRateResponse = wasm.query(&market_address, &QueryMsg::GetTaxRate {}).unwrap();
+        let err = sell_yes(None, Some(Decimal::percent(10))).unwrap_err().to_string();
+        assert!(err.contains("Tax rate"));
+        let err = sell_yes(Some(950), None).unwrap_err().to_string();
+        assert!(err.contains("below the minimum"));
+        sell_yes(Some(1), Some(tax_rate.tax_rate + Decimal::percent(1))).unwrap();
+    }
+
+    #[test]
+    fn test_fixed_odds_bets_lock_payouts() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let bettor = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(1000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { pricing_mode, .. } = &mut msg {
+            *pricing_mode = Some(PricingMode::FixedOdds);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let balance = |address: &str, denom: &str| -> u128 {
+            bank.query_balance(&QueryBalanceRequest {
+                address: address.to_string(),
+                denom: denom.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+            .parse()
+            .unwrap()
+        };
+        let set_line = |odds: Decimal| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::SetHouseLine {
+                    market_id: "test_market_2".to_string(),
+                    option: "Yes".to_string(),
+                    odds,
+                    liability_cap: Uint128::new(1000),
+                },
+                &[],
+                &admin,
+            )
+            .unwrap();
+        };
+        let bet_yes = |amount: u128| {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: "Yes".to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(amount, BUY_TOKEN)],
+                &bettor,
+            )
+        };
+
+        // Even odds: 95 after commission locks a payout of 190, which the house can't cover yet
+        set_line(Decimal::one());
+        let err = bet_yes(100).unwrap_err().to_string();
+        assert!(err.contains("to cover its payouts"));
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::FundLiquidity {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(500, BUY_TOKEN)],
+            &admin,
+        )
+        .unwrap();
+        bet_yes(100).unwrap();
+
+        // New odds only apply to new bets: 380 at 0.5 pays 570, the next one would pass the cap
+        set_line(Decimal::percent(50));
+        bet_yes(400).unwrap();
+        let err = bet_yes(400).unwrap_err().to_string();
+        assert!(err.contains("above the cap"));
+
+        let bets: BetsResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetBets {
+                    market_id: "test_market_2".to_string(),
+                    user: Addr::unchecked(bettor.address()),
+                },
+            )
+            .unwrap();
+        assert_eq!(bets.bets.len(), 2);
+        assert_eq!(bets.bets[0].odds, Decimal::one());
+        assert_eq!(bets.bets[0].payout, Uint128::new(190));
+        assert_eq!(bets.bets[1].odds, Decimal::percent(50));
+        assert_eq!(bets.bets[1].payout, Uint128::new(570));
+
+        let lines: HouseLinesResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetHouseLines {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(lines.bankroll, Uint128::new(500));
+        assert_eq!(lines.lines[0].liability, Uint128::new(760));
+
+        update_clp_feed_price(&wasm, &admin, &feed_addr, "2.0");
+        app.increase_time(TIME_TO_END);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // The locked payouts are paid whatever else was bet
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(760, &market.token_a.denom)],
+            &bettor,
+        )
+        .unwrap();
+        assert_eq!(balance(&bettor.address(), BUY_TOKEN), 500 + 760);
+
+        // The house takes back what is left of its bankroll and the stakes: 500 + 95 + 380 - 760
+        let admin_before = balance(&admin.address(), BUY_TOKEN);
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::WithdrawLiquidity {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+        assert_eq!(balance(&admin.address(), BUY_TOKEN), admin_before + 215);
+    }
+
+    #[test]
+    fn test_tax_curve_with_cap_and_grace_period() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let tax_config = TaxConfig {
+            curve: TaxCurve::Stepwise {
+                steps: vec![
+                    TaxStep {
+                        after: 3600 * 2,
+                        rate: Decimal::percent(20),
+                    },
+                    TaxStep {
+                        after: TIME_TO_END / 2,
+                        rate: Decimal::percent(80),
+                    },
+                ],
+            },
+            cap: Some(Decimal::percent(50)),
+            grace_period: 3600,
+        };
+
+        // A cap above 100% is rejected
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { tax_config: config, .. } = &mut msg {
+            *config = Some(TaxConfig {
+                cap: Some(Decimal::percent(150)),
+                ..tax_config.clone()
+            });
+        }
+        let result = wasm.execute(
+            &registry_address,
+            &msg,
+            &[coin(20_000_000, FEE_DENOM)],
+            &admin,
+        );
+        assert!(result.is_err());
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { tax_config: config, .. } = &mut msg {
+            *config = Some(tax_config.clone());
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        let simulate = || -> SimulateSellResponse {
+            wasm.query(
+                &market_address,
+                &QueryMsg::SimulateSell {
+                    option: "Yes".to_string(),
+                    amount: "1000".to_string(),
+                },
+            )
+            .unwrap()
+        };
+
+        // No tax during the grace period
+        let tax_rate: TaxRateResponse = wasm.query(&market_address, &QueryMsg::GetTaxRate {}).unwrap();
+        assert_eq!(tax_rate.tax_rate, Decimal::zero());
+        assert_eq!(tax_rate.tax_config, tax_config);
+        assert_eq!(simulate().amount_after_tax, "1000");
+
+        // Past the grace period but before the first step
+        app.increase_time(3600 + 600);
+        assert_eq!(simulate().tax_rate, Decimal::zero());
+
+        // First step
+        app.increase_time(3600);
+        let simulated = simulate();
+        assert_eq!(simulated.tax_rate, Decimal::percent(20));
+        assert_eq!(simulated.tax_amount, "200");
+        assert_eq!(simulated.amount_after_tax, "800");
+        assert_eq!(simulated.tax_config, tax_config);
+
+        // The second step is held at the cap
+        app.increase_time(TIME_TO_END / 2);
+        let simulated = simulate();
+        assert_eq!(simulated.tax_rate, Decimal::percent(50));
+        assert_eq!(simulated.amount_after_tax, "500");
+    }
+
+    #[test]
+    fn test_permissionless_resolution_pays_bounty() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let keeper = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            resolution_mode,
+            resolution_bounty,
+            ..
+        } = &mut msg
+        {
+            *resolution_mode = ResolutionMode::Permissionless;
+            *resolution_bounty = Uint128::from(30u128);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // 5% commission on 1000 is 50, 30 of it is held back for the bounty
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_2".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+
+        app.increase_time(TIME_TO_END);
+
+        // Any address can resolve the market and collects the bounty
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &keeper,
+        )
+        .unwrap();
+
+        let keeper_balance = bank
+            .query_balance(&QueryBalanceRequest {
+                address: keeper.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(keeper_balance, "30");
+    }
+
+    #[test]
+    fn test_bounty_taking_whole_commission_is_paid_once_final() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let arbitrator = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let keeper = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+        let balance = |address: String| -> String {
+            bank.query_balance(&QueryBalanceRequest {
+                address,
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount
+        };
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            resolution_mode,
+            resolution_bounty,
+            dispute_config,
+            ..
+        } = &mut msg
+        {
+            *resolution_mode = ResolutionMode::Permissionless;
+            *resolution_bounty = Uint128::from(100u128);
+            *dispute_config = Some(DisputeConfig {
+                arbitrator: Addr::unchecked(arbitrator.address()),
+                challenge_period: 3600,
+                bond: Uint128::from(500u128),
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // The whole 50 commission goes to the bounty reserve, the admin is sent nothing
+        let admin_before = balance(admin.address());
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_2".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+        assert_eq!(balance(admin.address()), admin_before);
+
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &keeper,
+        )
+        .unwrap();
+
+        // A proposed resolution can still be overturned, the bounty is held back
+        assert_eq!(balance(keeper.address()), "0");
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::FinalizeResolution {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &keeper,
+        );
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Challenge period has not ended yet"));
+
+        app.increase_time(3600);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::FinalizeResolution {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &user1,
+        )
+        .unwrap();
+        assert_eq!(balance(keeper.address()), "50");
+
+        // The bounty is only paid once, a final resolution can't be finalized again
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::FinalizeResolution {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &user1,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("No proposed resolution"));
+        assert_eq!(balance(keeper.address()), "50");
+    }
+
+    #[test]
+    fn test_disputed_resolution_overturned_by_arbitrator() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let arbitrator = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let challenger = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(500u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            dispute_config,
+            resolution_bounty,
+            ..
+        } = &mut msg
+        {
+            *dispute_config = Some(DisputeConfig {
+                arbitrator: Addr::unchecked(arbitrator.address()),
+                challenge_period: 3600,
+                bond: Uint128::from(500u128),
+            });
+            *resolution_bounty = Uint128::from(20u128);
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_2".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+
+        app.increase_time(TIME_TO_END);
+
+        // The price is still 1.0, "No" is proposed
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            market.status,
+            MarketStatus::ProposedResolution { .. }
+        ));
+
+        // The challenger disputes by posting the bond
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::DisputeResolution {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(500, BUY_TOKEN)],
+            &challenger,
+        )
+        .unwrap();
+
+        // Withdrawals are closed while the dispute is pending
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), &market.token_a.denom)],
+            &user1,
+        );
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("Market is not resolved yet"));
+
+        // Only the arbitrator can rule
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::ArbitrateDispute {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Yes".to_string(),
+            },
+            &[],
+            &admin,
+        );
+        assert!(result.is_err());
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::ArbitrateDispute {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Yes".to_string(),
+            },
+            &[],
+            &arbitrator,
+        )
+        .unwrap();
+
+        let resolution: ResolutionResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetResolution {
+                    market_id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(resolution.resolution.unwrap().winning_option.text, "Yes");
+        assert_eq!(resolution.dispute.unwrap().ruling.unwrap().text, "Yes");
+
+        // The resolution was overturned, the challenger gets the bond back and the bounty
+        let challenger_balance = bank
+            .query_balance(&QueryBalanceRequest {
+                address: challenger.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(challenger_balance, "520");
+
+        // Withdrawals open once the outcome is final
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), &market.token_a.denom)],
+            &user1,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_dispute_left_unruled_is_voided_after_deadline() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let arbitrator = app
+            .init_account(&[coin(100_000_000_000_000_000_000u128, FEE_DENOM)])
+            .unwrap();
+        let user1 = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let challenger = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(500u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket { dispute_config, .. } = &mut msg {
+            *dispute_config = Some(DisputeConfig {
+                arbitrator: Addr::unchecked(arbitrator.address()),
+                challenge_period: 3600,
+                bond: Uint128::from(500u128),
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::BuyShare {
+                market_id: "test_market_2".to_string(),
+                option: "Yes".to_string(),
+                min_amount_out: None,
+                min_odds: None,
+            },
+            &[coin(1000, BUY_TOKEN)],
+            &user1,
+        )
+        .unwrap();
+
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Resolve {
+                market_id: "test_market_2".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::DisputeResolution {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(500, BUY_TOKEN)],
+            &challenger,
+        )
+        .unwrap();
+
+        // The arbitrator never rules, the market is voided after the resolution deadline
+        let void = || {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::VoidMarket {
+                    market_id: "test_market_2".to_string(),
+                },
+                &[],
+                &user1,
+            )
+        };
+        assert!(void().is_err());
+
+        app.increase_time(DEFAULT_RESOLUTION_TIMEOUT);
+        void().unwrap();
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.status, MarketStatus::Void);
+
+        // The challenger gets the bond back
+        let challenger_balance = bank
+            .query_balance(&QueryBalanceRequest {
+                address: challenger.address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(challenger_balance, "500");
+    }
+
+    #[test]
+    fn test_three_option_market_pays_winner_whole_pot() {
+        let app = CoreumTestApp::new();
+        let admin = app
+            .init_account(&[
+                coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                coin(100_000_000_000_000_000_000u128, BUY_TOKEN),
+            ])
+            .unwrap();
+        let users: Vec<SigningAccount> = (0..3)
+            .map(|_| {
+                app.init_account(&[
+                    coin(100_000_000_000_000_000_000u128, FEE_DENOM),
+                    coin(1000u128, BUY_TOKEN),
+                ])
+                .unwrap()
+            })
+            .collect();
+        let wasm: Wasm<'_, CoreumTestApp> = Wasm::new(&app);
+        let bank = Bank::new(&app);
+
+        let (registry_address, _market_address, feed_addr) =
+            setup_registry_and_market(&wasm, &admin);
+
+        let mut msg = create_market_msg("test_market_2", &feed_addr);
+        if let RegistryExecuteMsg::CreateMarket {
+            market_type,
+            resolvers,
+            ..
+        } = &mut msg
+        {
+            *market_type = MarketType::Event {
+                options: vec!["Home".to_string(), "Away".to_string(), "Draw".to_string()],
+            };
+            *resolvers = Some(ResolverSet {
+                members: vec![Addr::unchecked(admin.address())],
+                threshold: 1,
+            });
+        }
+        let market_address = create_market(&wasm, &admin, &registry_address, &msg);
+
+        // One user on each option
+        for (user, option) in users.iter().zip(["Home", "Away", "Draw"]) {
+            wasm.execute(
+                &market_address,
+                &ExecuteMsg::BuyShare {
+                    market_id: "test_market_2".to_string(),
+                    option: option.to_string(),
+                    min_amount_out: None,
+                    min_odds: None,
+                },
+                &[coin(1000, BUY_TOKEN)],
+                user,
+            )
+            .unwrap();
+        }
+
+        let market: MarketResponse = wasm
+            .query(
+                &market_address,
+                &QueryMsg::GetMarket {
+                    id: "test_market_2".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(market.options, vec!["Home", "Away", "Draw"]);
+        assert_eq!(market.num_bettors, 3);
+        assert_eq!(
+            market.total_value.amount,
+            (3 * calculate_net_amount(1000)).to_string()
+        );
+        assert_eq!(market.options_with_odds.len(), 3);
+        for option in &market.options_with_odds {
+            assert_eq!(option.total_staked, calculate_net_amount(1000).to_string());
+            // Every other option staked twice as much
+            assert_eq!(option.odds, "2");
+        }
+
+        app.increase_time(TIME_TO_END);
+
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::SubmitOutcome {
+                market_id: "test_market_2".to_string(),
+                winning_option: "Draw".to_string(),
+            },
+            &[],
+            &admin,
+        )
+        .unwrap();
+
+        // Losing options are worth nothing
+        let home_denom = &market.options_with_odds[0].token_denom;
+        let result = wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), home_denom)],
+            &users[0],
+        );
+        assert!(result.is_err());
+
+        // The third option takes the stakes of both others
+        let draw_denom = &market.options_with_odds[2].token_denom;
+        wasm.execute(
+            &market_address,
+            &ExecuteMsg::Withdraw {
+                market_id: "test_market_2".to_string(),
+            },
+            &[coin(calculate_net_amount(1000), draw_denom)],
+            &users[2],
+        )
+        .unwrap();
+
+        let winner_balance = bank
+            .query_balance(&QueryBalanceRequest {
+                address: users[2].address().to_string(),
+                denom: BUY_TOKEN.to_string(),
+            })
+            .unwrap()
+            .balance
+            .unwrap()
+            .amount;
+        assert_eq!(winner_balance, (3 * calculate_net_amount(1000)).to_string());
+    }
+
+    #[test]
+    fn test_migrate_rewrites_legacy_two_option_state() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(
+            deps.as_mut().storage,
+            "crates.io:cruise-control-prediction-market",
+            "0.0.1",
+        )
+        .unwrap();
+
+        let pairs = vec![
+            MarketOption {
+                text: "Yes".to_string(),
+                associated_token_denom: "truthyes_test_market_1-contract".to_string(),
+            },
+            MarketOption {
+                text: "No".to_string(),
+                associated_token_denom: "truthno_test_market_1-contract".to_string(),
+            },
+        ];
+        let end_time = Timestamp::from_seconds(1_000_000 + TIME_TO_END);
+        LEGACY_CONFIG
+            .save(
+                deps.as_mut().storage,
+                &LegacyConfig {
+                    id: "test_market_1".to_string(),
+                    admin: Addr::unchecked("admin"),
+                    commission_rate: Uint128::from(COMMISSION_RATE_BPS),
+                    pairs: pairs.clone(),
+                    buy_token: BUY_TOKEN.to_string(),
+                    banner_url: String::new(),
+                    description: String::new(),
+                    title: String::new(),
+                    start_time: Timestamp::from_seconds(1_000_000),
+                    end_time,
+                    oracle: Addr::unchecked("feed"),
+                    resolution_source: "feed".to_string(),
+                    asset_to_track: "CORE".to_string(),
+                    market_type: MarketType::UpDown,
+                    target_price: Decimal::from_str("1.5").unwrap(),
+                    initial_price: Decimal::one(),
+                },
+            )
+            .unwrap();
+        LEGACY_MARKET_STATE
+            .save(
+                deps.as_mut().storage,
+                &LegacyMarketState {
+                    status: MarketStatus::Active,
+                    total_value: CoreumCoin {
+                        denom: BUY_TOKEN.to_string(),
+                        amount: "1425".to_string(),
+                    },
+                    num_bettors: 2,
+                    total_stake_option_a: Uint128::from(950u128),
+                    total_stake_option_b: Uint128::from(475u128),
+                    volume: Uint128::from(1500u128),
+                },
+            )
+            .unwrap();
+        for (user, option, amount) in [("user1", "Yes", 950u128), ("user2", "No", 475u128)] {
+            SHARES
+                .save(
+                    deps.as_mut().storage,
+                    (&Addr::unchecked(user), option),
+                    &Share {
+                        amount: Uint128::from(amount),
+                        has_withdrawn: false,
+                    },
+                )
+                .unwrap();
+        }
+        // A position already paid out is not outstanding anymore
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                (&Addr::unchecked("user3"), "Yes"),
+                &Share {
+                    amount: Uint128::from(300u128),
+                    has_withdrawn: true,
+                },
+            )
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.pairs, pairs);
+        assert_eq!(
+            config.resolution_deadline,
+            end_time.plus_seconds(DEFAULT_RESOLUTION_TIMEOUT)
+        );
+        assert_eq!(config.pricing_mode, PricingMode::Parimutuel);
+        assert_eq!(config.resolution_mode, ResolutionMode::AdminOnly);
+
+        // Legacy UpDown markets keep settling against target_price
+        let strike = STRIKE_PRICE.load(deps.as_ref().storage).unwrap();
+        assert_eq!(strike.price, Decimal::from_str("1.5").unwrap());
+
+        let market_state = MARKET_STATE.load(deps.as_ref().storage).unwrap();
+        assert_eq!(market_state.num_bettors, 2);
+        assert_eq!(market_state.bounty_reserve, Uint128::zero());
+        let totals = market_state
+            .total_stakes(deps.as_ref().storage, &config)
+            .unwrap();
+        assert_eq!(totals, vec![Uint128::from(950u128), Uint128::from(475u128)]);
+        assert_eq!(
+            SHARE_TOTALS.load(deps.as_ref().storage, "Yes").unwrap(),
+            Uint128::from(950u128)
+        );
+        assert_eq!(
+            SHARE_TOTALS.load(deps.as_ref().storage, "No").unwrap(),
+            Uint128::from(475u128)
+        );
+
+        // Migrating again leaves the new layout untouched
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(CONFIG.load(deps.as_ref().storage).unwrap(), config);
+    }
+
     // Helper function to setup clp feed with specific price
 }
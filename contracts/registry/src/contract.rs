@@ -56,9 +56,20 @@ pub fn execute(
             end_time,
             resolution_source,
             asset_to_track,
+            second_asset_to_track,
             market_type,
             target_price,
             oracle,
+            resolution_timeout,
+            max_price_staleness,
+            resolution_mode,
+            resolution_bounty,
+            dispute_config,
+            resolvers,
+            oracle_quorum,
+            twap_window,
+            pricing_mode,
+            tax_config,
         } => execute::execute_create_market(
             deps,
             env,
@@ -72,9 +83,20 @@ pub fn execute(
             end_time,
             resolution_source,
             asset_to_track,
+            second_asset_to_track,
             market_type,
             target_price,
             oracle,
+            resolution_timeout,
+            max_price_staleness,
+            resolution_mode,
+            resolution_bounty,
+            dispute_config,
+            resolvers,
+            oracle_quorum,
+            twap_window,
+            pricing_mode,
+            tax_config,
         ),
     }
 }
@@ -6,10 +6,16 @@ use cosmwasm_std::{
 use crate::error::ContractError;
 use crate::state::{MarketInfo, MarketOption, MarketStatus, CONFIG, MARKETS};
 
-use market::msg::{InstantiateMsg as MarketInstantiateMsg, MarketType};
+use market::msg::{
+    InstantiateMsg as MarketInstantiateMsg, MarketType, PricingMode, ResolutionMode, TaxConfig,
+};
+use market::state::{DisputeConfig, OracleQuorum, ResolverSet};
 
 use utils::{address::derive_address2, hashing::hash_data, validation::validate_funds};
 
+// Coreum charges 10 COREUM for each FT issued, and a market issues one FT per option
+const ISSUE_FEE_PER_OPTION: u128 = 10_000_000;
+
 pub fn execute_create_market(
     deps: DepsMut,
     env: Env,
@@ -23,9 +29,20 @@ pub fn execute_create_market(
     end_time: Timestamp,
     resolution_source: String,
     asset_to_track: String,
+    second_asset_to_track: Option<String>,
     market_type: MarketType,
     target_price: Decimal,
     oracle: Addr,
+    resolution_timeout: Option<u64>,
+    max_price_staleness: Option<u64>,
+    resolution_mode: ResolutionMode,
+    resolution_bounty: Uint128,
+    dispute_config: Option<DisputeConfig>,
+    resolvers: Option<ResolverSet>,
+    oracle_quorum: Option<OracleQuorum>,
+    twap_window: Option<u64>,
+    pricing_mode: Option<PricingMode>,
+    tax_config: Option<TaxConfig>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let admin = config.admin.clone();
@@ -36,8 +53,9 @@ pub fn execute_create_market(
         )));
     }
 
+    let issue_fee = ISSUE_FEE_PER_OPTION * market_type.get_options().len() as u128;
     let payment: cosmwasm_std::Uint128 =
-        validate_funds(&info, "ucore", Some(Uint128::from(20_000_000u128)))
+        validate_funds(&info, "ucore", Some(Uint128::from(issue_fee)))
             .map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
 
     // Check if the market ID already exists
@@ -59,10 +77,21 @@ pub fn execute_create_market(
         start_time: start_time.clone(),
         resolution_source: resolution_source.clone(),
         asset_to_track: asset_to_track.clone(),
+        second_asset_to_track: second_asset_to_track.clone(),
         market_type: market_type.clone(),
         target_price: target_price.clone(),
         commission_rate: config.commission_rate,
         oracle: oracle.clone(),
+        resolution_timeout,
+        max_price_staleness,
+        resolution_mode: resolution_mode.clone(),
+        resolution_bounty,
+        dispute_config: dispute_config.clone(),
+        resolvers: resolvers.clone(),
+        oracle_quorum: oracle_quorum.clone(),
+        twap_window,
+        pricing_mode: pricing_mode.clone(),
+        tax_config: tax_config.clone(),
     };
 
     let registry_canonical_addr = deps.api.addr_canonicalize(env.contract.address.as_str())?;
@@ -91,8 +120,19 @@ pub fn execute_create_market(
         commission_rate: config.commission_rate,
         market_code_id: config.market_code_id,
         asset_to_track: asset_to_track,
+        second_asset_to_track,
         market_type: market_type,
         target_price: target_price,
+        resolution_timeout,
+        max_price_staleness,
+        resolution_mode: Some(resolution_mode),
+        resolution_bounty: Some(resolution_bounty),
+        dispute_config,
+        resolvers,
+        oracle_quorum,
+        twap_window,
+        pricing_mode,
+        tax_config,
     };
 
     MARKETS.save(deps.storage, &id, &market_info)?;
@@ -107,7 +147,7 @@ pub fn execute_create_market(
             code_id: config.market_code_id,
             label: "cruise_control_prediction_market".to_string(),
             msg: to_json_binary(&market_instantiate_msg)?,
-            //we need to pay for the two FTs to be created (20 COREUM)
+            //we need to pay for the FTs of every option to be created (10 COREUM each)
             funds: vec![Coin {
                 denom: "ucore".to_string(),
                 amount: payment.clone(),
@@ -1,7 +1,8 @@
 use crate::state::{Config, MarketInfo};
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Timestamp, Uint128, Decimal};
-use market::msg::MarketType;
+use market::msg::{MarketType, PricingMode, ResolutionMode, TaxConfig};
+use market::state::{DisputeConfig, OracleQuorum, ResolverSet};
 
 #[cw_serde]
 pub struct MigrateMsg {}
@@ -25,9 +26,20 @@ pub enum ExecuteMsg {
         title: String,
         resolution_source: String,
         asset_to_track: String,
+        second_asset_to_track: Option<String>,
         market_type: MarketType,
         target_price: Decimal,
         oracle: Addr,
+        resolution_timeout: Option<u64>,
+        max_price_staleness: Option<u64>,
+        resolution_mode: ResolutionMode,
+        resolution_bounty: Uint128,
+        dispute_config: Option<DisputeConfig>,
+        resolvers: Option<ResolverSet>,
+        oracle_quorum: Option<OracleQuorum>,
+        twap_window: Option<u64>,
+        pricing_mode: Option<PricingMode>,
+        tax_config: Option<TaxConfig>,
     },
 }
 
@@ -1,7 +1,8 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
-use market::msg::MarketType;
+use market::msg::{MarketType, PricingMode, ResolutionMode, TaxConfig};
+use market::state::{DisputeConfig, OracleQuorum, ResolverSet};
 
 /// Config
 
@@ -62,10 +63,19 @@ pub struct MarketInfo {
     pub commission_rate: Uint128,
     pub market_code_id: u64,
     pub asset_to_track: String,
+    pub second_asset_to_track: Option<String>, // Relative markets only
     pub market_type: MarketType,
     pub target_price: Decimal,
- 
-    
+    pub resolution_timeout: Option<u64>,
+    pub max_price_staleness: Option<u64>,
+    pub resolution_mode: Option<ResolutionMode>, // None for markets listed before resolution modes
+    pub resolution_bounty: Option<Uint128>,
+    pub dispute_config: Option<DisputeConfig>,
+    pub resolvers: Option<ResolverSet>, // Submit the outcome of Event markets
+    pub oracle_quorum: Option<OracleQuorum>, // Settle on the median of several feeds
+    pub twap_window: Option<u64>, // Settle on the TWAP of this many seconds before end_time
+    pub pricing_mode: Option<PricingMode>, // Parimutuel pool, or LMSR prices
+    pub tax_config: Option<TaxConfig>, // Early-exit tax curve, None: linear
 }
 
 /// Maps market_id -> MarketInfo (e.g., "truth_market_1" -> MarketInfo)
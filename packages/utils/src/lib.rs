@@ -1,7 +1,9 @@
 pub mod address;
 pub mod hashing;
 pub mod error;
+pub mod lmsr;
 pub mod math;
+pub mod oracle;
 pub mod types;
 pub mod validation;
 pub mod traits;
\ No newline at end of file
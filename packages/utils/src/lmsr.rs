@@ -0,0 +1,153 @@
+//! Logarithmic market scoring rule. The cost of holding quantities q with liquidity b is
+//! C(q) = b * ln(sum(e^(q_i / b))), and the price of option i is e^(q_i / b) / sum(e^(q_j / b)).
+//! Exponentials are taken relative to max(q) (or to the target cost) so they stay within [0, 1].
+
+use cosmwasm_std::{Decimal256, StdError, StdResult};
+
+use crate::math::{exp_neg, ln};
+
+/// e^((q_i - reference) / b) for each quantity, with every q_i <= reference
+fn scaled_exponentials(
+    quantities: &[Decimal256],
+    liquidity: Decimal256,
+    reference: Decimal256,
+) -> Vec<Decimal256> {
+    quantities
+        .iter()
+        .map(|quantity| exp_neg((reference - *quantity) / liquidity))
+        .collect()
+}
+
+fn max_quantity(quantities: &[Decimal256]) -> Decimal256 {
+    quantities.iter().max().copied().unwrap_or_default()
+}
+
+/// C(q) = max(q) + b * ln(sum(e^((q_i - max(q)) / b)))
+pub fn cost(quantities: &[Decimal256], liquidity: Decimal256) -> StdResult<Decimal256> {
+    let max = max_quantity(quantities);
+    let sum: Decimal256 = scaled_exponentials(quantities, liquidity, max).into_iter().sum();
+
+    Ok(max + liquidity * ln(sum)?)
+}
+
+/// Instant price of every option, they add up to 1
+pub fn prices(quantities: &[Decimal256], liquidity: Decimal256) -> Vec<Decimal256> {
+    let exponentials = scaled_exponentials(quantities, liquidity, max_quantity(quantities));
+    let sum: Decimal256 = exponentials.iter().copied().sum();
+
+    exponentials.into_iter().map(|exponential| exponential / sum).collect()
+}
+
+/// Quantity of option `index` that `amount` buys: the d with C(q + d * e_index) = C(q) + amount.
+/// With T = C(q) + amount and X = sum over j != index of e^((q_j - T) / b),
+/// q_index + d = T - b * ln(1 / (1 - X))
+pub fn quantity_for_cost(
+    quantities: &[Decimal256],
+    liquidity: Decimal256,
+    index: usize,
+    amount: Decimal256,
+) -> StdResult<Decimal256> {
+    let target = cost(quantities, liquidity)? + amount;
+
+    let others: Decimal256 = scaled_exponentials(quantities, liquidity, target)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, exponential)| exponential)
+        .sum();
+    if others >= Decimal256::one() {
+        return Err(StdError::generic_err("LMSR cost out of range"));
+    }
+
+    let new_quantity = target
+        .checked_sub(liquidity * ln(Decimal256::one() / (Decimal256::one() - others))?)
+        .map_err(|_| StdError::generic_err("LMSR quantity out of range"))?;
+
+    new_quantity
+        .checked_sub(quantities[index])
+        .map_err(|_| StdError::generic_err("LMSR quantity out of range"))
+}
+
+/// Most the market maker can lose: b * ln(number of options)
+pub fn max_loss(liquidity: Decimal256, options: usize) -> StdResult<Decimal256> {
+    Ok(liquidity * ln(Decimal256::from_ratio(options as u128, 1u128))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> Decimal256 {
+        Decimal256::from_str(value).unwrap()
+    }
+
+    fn assert_close(actual: Decimal256, expected: Decimal256) {
+        assert!(
+            actual.abs_diff(expected) <= decimal("0.000000001"),
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn cost_and_prices_of_an_empty_market() {
+        let liquidity = decimal("1000");
+        let quantities = [Decimal256::zero(); 2];
+
+        // b * ln(2), the subsidy funded before trading
+        assert_close(
+            cost(&quantities, liquidity).unwrap(),
+            decimal("693.147180559945309"),
+        );
+        assert_eq!(
+            cost(&quantities, liquidity).unwrap(),
+            max_loss(liquidity, 2).unwrap()
+        );
+        assert_eq!(prices(&quantities, liquidity), vec![decimal("0.5"); 2]);
+    }
+
+    #[test]
+    fn prices_follow_quantities() {
+        let liquidity = decimal("100");
+        let quantities = [decimal("250"), decimal("50"), Decimal256::zero()];
+
+        let prices = prices(&quantities, liquidity);
+        assert!(prices[0] > prices[1] && prices[1] > prices[2]);
+        assert_close(prices.iter().copied().sum(), Decimal256::one());
+        // Prices are in the ratio e^((q_0 - q_1) / b)
+        assert_close(prices[0] / prices[1], decimal("7.389056098930650227"));
+    }
+
+    #[test]
+    fn quantity_for_cost_inverts_cost() {
+        let liquidity = decimal("1000");
+        let quantities = [decimal("1428"), decimal("300")];
+
+        for (index, amount) in [(0, "950"), (1, "1"), (1, "25000")] {
+            let amount = decimal(amount);
+            let bought = quantity_for_cost(&quantities, liquidity, index, amount).unwrap();
+
+            let mut after = quantities;
+            after[index] += bought;
+            assert_close(
+                cost(&after, liquidity).unwrap() - cost(&quantities, liquidity).unwrap(),
+                amount,
+            );
+        }
+    }
+
+    #[test]
+    fn buying_then_selling_returns_the_cost() {
+        let liquidity = decimal("1000");
+        let start = [Decimal256::zero(); 2];
+
+        // 950 buys about 1428 "Yes", as in the market integration tests
+        let bought = quantity_for_cost(&start, liquidity, 0, decimal("950")).unwrap();
+        assert_eq!(bought.to_uint_floor().to_string(), "1428");
+
+        // Selling them back is worth the cost difference, which is what was paid
+        let after = [bought, Decimal256::zero()];
+        let sell_value = cost(&after, liquidity).unwrap() - cost(&start, liquidity).unwrap();
+        assert_close(sell_value, decimal("950"));
+    }
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::{Decimal, StdError, Uint128};
+use cosmwasm_std::{Decimal, Decimal256, StdError, Uint128};
 
 pub fn mul_128_by_decimal(a: Uint128, b: Decimal) -> Result<Uint128, StdError> {
     let a_decimal = Decimal::from_atomics(a, 0)
@@ -17,3 +17,143 @@ pub fn div_128_by_decimal(a: Uint128, b: Decimal) -> Result<Decimal, StdError> {
 
     Ok(result)
 }
+
+/// Median of `values`, the mean of the two middle values for an even count. None if empty
+pub fn median(values: &mut [Decimal]) -> Option<Decimal> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / Decimal::percent(200))
+    } else {
+        Some(values[mid])
+    }
+}
+
+// ln(2) with the 18 decimals of Decimal256
+const LN_2: Decimal256 = Decimal256::raw(693_147_180_559_945_309);
+
+/// e^(-x) for x >= 0. Results below the 18 decimals of precision are zero
+pub fn exp_neg(x: Decimal256) -> Decimal256 {
+    // e^-100 is far below the smallest representable value
+    if x > Decimal256::from_ratio(100u128, 1u128) {
+        return Decimal256::zero();
+    }
+
+    // x = k * ln(2) + r with 0 <= r < ln(2), so e^-x = 1 / (2^k * e^r)
+    let mut k = 0u32;
+    let mut r = x;
+    while r >= LN_2 {
+        r -= LN_2;
+        k += 1;
+    }
+
+    // Taylor series of e^r, r < 0.7 converges well within 30 terms
+    let mut term = Decimal256::one();
+    let mut exp_r = Decimal256::one();
+    for n in 1u128..30 {
+        term = term * r / Decimal256::from_ratio(n, 1u128);
+        if term.is_zero() {
+            break;
+        }
+        exp_r += term;
+    }
+
+    let mut divisor = exp_r;
+    for _ in 0..k {
+        divisor = divisor + divisor;
+    }
+    Decimal256::one() / divisor
+}
+
+/// Natural logarithm of x >= 1
+pub fn ln(x: Decimal256) -> Result<Decimal256, StdError> {
+    if x < Decimal256::one() {
+        return Err(StdError::generic_err("ln is only defined for values >= 1"));
+    }
+
+    // x = 2^k * z with 1 <= z < 2, so ln(x) = k * ln(2) + ln(z)
+    let two = Decimal256::from_ratio(2u128, 1u128);
+    let mut z = x;
+    let mut k = 0u128;
+    while z >= two {
+        z /= two;
+        k += 1;
+    }
+
+    // ln(z) = 2 * atanh(t) with t = (z - 1) / (z + 1) < 1/3
+    let t = (z - Decimal256::one()) / (z + Decimal256::one());
+    let t_squared = t * t;
+    let mut power = t;
+    let mut series = Decimal256::zero();
+    for n in 0u128..40 {
+        let term = power / Decimal256::from_ratio(2 * n + 1, 1u128);
+        if term.is_zero() {
+            break;
+        }
+        series += term;
+        power *= t_squared;
+    }
+
+    Ok(LN_2 * Decimal256::from_ratio(k, 1u128) + series + series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn assert_close(actual: Decimal256, expected: &str) {
+        let expected = Decimal256::from_str(expected).unwrap();
+        let error = actual.abs_diff(expected);
+        assert!(
+            error <= Decimal256::from_str("0.000000000001").unwrap(),
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn exp_neg_matches_known_values() {
+        assert_eq!(exp_neg(Decimal256::zero()), Decimal256::one());
+        assert_close(exp_neg(LN_2), "0.5");
+        assert_close(exp_neg(Decimal256::one()), "0.367879441171442321");
+        assert_close(
+            exp_neg(Decimal256::from_str("10").unwrap()),
+            "0.000045399929762484",
+        );
+        assert_eq!(
+            exp_neg(Decimal256::from_str("100.5").unwrap()),
+            Decimal256::zero()
+        );
+    }
+
+    #[test]
+    fn ln_matches_known_values() {
+        assert_eq!(ln(Decimal256::one()).unwrap(), Decimal256::zero());
+        assert_close(
+            ln(Decimal256::from_str("2").unwrap()).unwrap(),
+            "0.693147180559945309",
+        );
+        assert_close(
+            ln(Decimal256::from_str("2.718281828459045235").unwrap()).unwrap(),
+            "1",
+        );
+        assert_close(
+            ln(Decimal256::from_str("1000").unwrap()).unwrap(),
+            "6.907755278982137052",
+        );
+        assert!(ln(Decimal256::from_str("0.5").unwrap()).is_err());
+    }
+
+    #[test]
+    fn ln_inverts_exp_neg() {
+        for x in ["0.25", "1.5", "7", "15"] {
+            let x = Decimal256::from_str(x).unwrap();
+            let inverse = ln(Decimal256::one() / exp_neg(x)).unwrap();
+            assert!(inverse.abs_diff(x) <= Decimal256::from_str("0.000000001").unwrap());
+        }
+    }
+}
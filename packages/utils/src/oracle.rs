@@ -0,0 +1,441 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clp_feed_interface::ClpFeedQuerier;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Binary, Decimal, QuerierWrapper, QueryRequest, Timestamp, WasmQuery};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::error::ContractError;
+
+/// A price reported by an oracle and the time it was produced
+#[cw_serde]
+pub struct OraclePrice {
+    pub price: Decimal,
+    pub timestamp: Timestamp,
+}
+
+/// A source of asset prices a market can track and settle on
+pub trait PriceOracle {
+    /// Latest price of `asset`, None if the source has no price for it
+    fn query_price(
+        &self,
+        querier: &QuerierWrapper,
+        asset: &str,
+    ) -> Result<Option<OraclePrice>, ContractError>;
+}
+
+/// Which oracle adapter a market uses, parsed from its resolution source:
+/// - `fixed:<price>`: a manual price, seeded with `<price>` and posted by the market admin
+/// - `wasm:<price path>:<timestamp path>:<query>`: a smart query on the oracle contract,
+///   `{asset}` in the query is replaced by the asset and the paths are dot separated
+/// - anything else (a URL, a rules hash...): the clp feed
+#[cw_serde]
+pub enum OracleSource {
+    ClpFeed,
+    Fixed { price: Decimal },
+    WasmQuery(WasmQuerySource),
+}
+
+#[cw_serde]
+pub struct WasmQuerySource {
+    // JSON query message, with `{asset}` standing for the asset to price
+    pub query: String,
+    // Path to the price in the response, e.g. "price.value" or "prices.0.price"
+    pub price_path: String,
+    // Path to the price timestamp (nanoseconds) in the response
+    pub timestamp_path: String,
+}
+
+impl FromStr for OracleSource {
+    type Err = ContractError;
+
+    fn from_str(resolution_source: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| ContractError::InvalidConfig {
+            reason: format!("Invalid resolution source: {}", reason),
+        };
+
+        if let Some(price) = resolution_source.strip_prefix("fixed:") {
+            let price = Decimal::from_str(price.trim()).map_err(|_| invalid("bad fixed price"))?;
+            if price.is_zero() {
+                return Err(invalid("fixed price must be positive"));
+            }
+            return Ok(OracleSource::Fixed { price });
+        }
+
+        if let Some(source) = resolution_source.strip_prefix("wasm:") {
+            let mut parts = source.splitn(3, ':');
+            let (Some(price_path), Some(timestamp_path), Some(query)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(invalid(
+                    "expected wasm:<price path>:<timestamp path>:<query>",
+                ));
+            };
+            if price_path.is_empty() || timestamp_path.is_empty() || query.is_empty() {
+                return Err(invalid("empty wasm query or path"));
+            }
+            return Ok(OracleSource::WasmQuery(WasmQuerySource {
+                query: query.to_string(),
+                price_path: price_path.to_string(),
+                timestamp_path: timestamp_path.to_string(),
+            }));
+        }
+
+        Ok(OracleSource::ClpFeed)
+    }
+}
+
+/// Prices from a clp feed contract
+pub struct ClpFeedOracle {
+    pub feed: Addr,
+}
+
+impl PriceOracle for ClpFeedOracle {
+    fn query_price(
+        &self,
+        querier: &QuerierWrapper,
+        asset: &str,
+    ) -> Result<Option<OraclePrice>, ContractError> {
+        let response =
+            ClpFeedQuerier::new(querier, self.feed.clone()).query_price(asset.to_string())?;
+
+        response
+            .price
+            .map(|price_info| {
+                Ok(OraclePrice {
+                    price: parse_price(&price_info.price)?,
+                    timestamp: price_info.timestamp,
+                })
+            })
+            .transpose()
+    }
+}
+
+/// A manually set price, the same for every asset
+pub struct FixedPriceOracle {
+    pub price: Option<OraclePrice>,
+}
+
+impl PriceOracle for FixedPriceOracle {
+    fn query_price(
+        &self,
+        _querier: &QuerierWrapper,
+        _asset: &str,
+    ) -> Result<Option<OraclePrice>, ContractError> {
+        Ok(self.price.clone())
+    }
+}
+
+/// Prices read from the response of a smart query on any wasm contract
+pub struct WasmQueryOracle {
+    pub contract: Addr,
+    pub source: WasmQuerySource,
+}
+
+impl PriceOracle for WasmQueryOracle {
+    fn query_price(
+        &self,
+        querier: &QuerierWrapper,
+        asset: &str,
+    ) -> Result<Option<OraclePrice>, ContractError> {
+        let msg = self.source.query.replace("{asset}", asset);
+        let response: JsonValue = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: self.contract.to_string(),
+            msg: Binary::new(msg.into_bytes()),
+        }))?;
+
+        let price = match response.at_path(&self.source.price_path) {
+            Some(JsonValue::Null) | None => return Ok(None),
+            Some(value) => parse_price(
+                value
+                    .as_scalar()
+                    .ok_or_else(|| oracle_error("price is not a number"))?,
+            )?,
+        };
+        let timestamp = response
+            .at_path(&self.source.timestamp_path)
+            .and_then(JsonValue::as_scalar)
+            .and_then(|nanos| nanos.parse::<u64>().ok())
+            .ok_or_else(|| oracle_error("missing or invalid timestamp"))?;
+
+        Ok(Some(OraclePrice {
+            price,
+            timestamp: Timestamp::from_nanos(timestamp),
+        }))
+    }
+}
+
+impl WasmQuerySource {
+    /// Checks the query template is valid JSON
+    pub fn validate(&self) -> Result<(), ContractError> {
+        cosmwasm_std::from_json::<JsonValue>(self.query.replace("{asset}", "asset"))
+            .map(|_| ())
+            .map_err(|_| ContractError::InvalidConfig {
+                reason: "Invalid resolution source: query is not valid JSON".to_string(),
+            })
+    }
+}
+
+fn parse_price(price: &str) -> Result<Decimal, ContractError> {
+    Decimal::from_str(price).map_err(|_| oracle_error("invalid price format"))
+}
+
+fn oracle_error(reason: &str) -> ContractError {
+    ContractError::PriceOracleError {
+        reason: reason.to_string(),
+    }
+}
+
+/// Minimal JSON tree used to walk smart query responses of unknown shape.
+/// Numbers and booleans are kept as strings since contracts can't use floats
+#[derive(Debug, Clone, PartialEq)]
+enum JsonValue {
+    Null,
+    Scalar(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Value at a dot separated path of object keys and array indexes
+    fn at_path(&self, path: &str) -> Option<&JsonValue> {
+        path.split('.')
+            .try_fold(self, |value, segment| match value {
+                JsonValue::Object(fields) => fields
+                    .iter()
+                    .find(|(key, _)| key == segment)
+                    .map(|(_, value)| value),
+                JsonValue::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            })
+    }
+
+    fn as_scalar(&self) -> Option<&str> {
+        match self {
+            JsonValue::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonValueVisitor)
+    }
+}
+
+struct JsonValueVisitor;
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Scalar(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Scalar(value.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Scalar(value.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::Scalar(value.to_string()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(JsonValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut fields = Vec::new();
+        while let Some((key, value)) = map.next_entry()? {
+            fields.push((key, value));
+        }
+        Ok(JsonValue::Object(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockQuerier;
+    use cosmwasm_std::{from_json, ContractResult, Empty, SystemResult};
+
+    /// Prices `asset` through a wasm oracle answering every smart query with `response`
+    fn query_wasm_oracle(
+        price_path: &str,
+        timestamp_path: &str,
+        response: &'static str,
+    ) -> Result<Option<OraclePrice>, ContractError> {
+        let mut querier = MockQuerier::<Empty>::new(&[]);
+        querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { msg, .. } => {
+                // The asset is substituted in the query template
+                assert_eq!(msg.as_slice(), br#"{"price":{"denom":"CORE"}}"#);
+                SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        let oracle = WasmQueryOracle {
+            contract: Addr::unchecked("oracle"),
+            source: WasmQuerySource {
+                query: r#"{"price":{"denom":"{asset}"}}"#.to_string(),
+                price_path: price_path.to_string(),
+                timestamp_path: timestamp_path.to_string(),
+            },
+        };
+        oracle.query_price(&QuerierWrapper::new(&querier), "CORE")
+    }
+
+    fn reason(result: Result<Option<OraclePrice>, ContractError>) -> String {
+        match result {
+            Err(ContractError::PriceOracleError { reason }) => reason,
+            other => panic!("expected an oracle error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn json_value_walks_objects_and_arrays() {
+        let value: JsonValue =
+            from_json(br#"{"prices":[{"price":"1.5","live":true}],"height":12,"next":null}"#)
+                .unwrap();
+
+        assert_eq!(
+            value.at_path("prices.0.price"),
+            Some(&JsonValue::Scalar("1.5".to_string()))
+        );
+        assert_eq!(
+            value.at_path("prices.0.live"),
+            Some(&JsonValue::Scalar("true".to_string()))
+        );
+        assert_eq!(
+            value.at_path("height"),
+            Some(&JsonValue::Scalar("12".to_string()))
+        );
+        assert_eq!(value.at_path("next"), Some(&JsonValue::Null));
+
+        // Missing keys, out of range or non numeric indexes and paths through scalars
+        assert_eq!(value.at_path("price"), None);
+        assert_eq!(value.at_path("prices.1.price"), None);
+        assert_eq!(value.at_path("prices.first.price"), None);
+        assert_eq!(value.at_path("height.value"), None);
+        assert_eq!(value.at_path(""), None);
+    }
+
+    #[test]
+    fn wasm_oracle_reads_price_and_timestamp() {
+        let price = query_wasm_oracle(
+            "data.price",
+            "data.updated",
+            r#"{"data":{"price":"1.25","updated":"1700000000000000000"}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(price.price, Decimal::from_str("1.25").unwrap());
+        assert_eq!(
+            price.timestamp,
+            Timestamp::from_nanos(1_700_000_000_000_000_000)
+        );
+
+        // Integer prices and timestamps work as JSON numbers too
+        let price = query_wasm_oracle("price", "time", r#"{"price":2,"time":1700000000000000000}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(price.price, Decimal::from_str("2").unwrap());
+        assert_eq!(
+            price.timestamp,
+            Timestamp::from_nanos(1_700_000_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn wasm_oracle_without_price_returns_none() {
+        let null_price = query_wasm_oracle("price", "time", r#"{"price":null,"time":"1"}"#);
+        assert_eq!(null_price.unwrap(), None);
+
+        let missing_price = query_wasm_oracle("price", "time", r#"{"time":"1"}"#);
+        assert_eq!(missing_price.unwrap(), None);
+    }
+
+    #[test]
+    fn wasm_oracle_rejects_bad_prices() {
+        let object_price = query_wasm_oracle("price", "time", r#"{"price":{"v":"1"},"time":"1"}"#);
+        assert_eq!(reason(object_price), "price is not a number");
+
+        let text_price = query_wasm_oracle("price", "time", r#"{"price":"abc","time":"1"}"#);
+        assert_eq!(reason(text_price), "invalid price format");
+
+        let negative_price = query_wasm_oracle("price", "time", r#"{"price":-1,"time":"1"}"#);
+        assert_eq!(reason(negative_price), "invalid price format");
+    }
+
+    #[test]
+    fn wasm_oracle_rejects_bad_timestamps() {
+        for response in [
+            r#"{"price":"1.5"}"#,
+            r#"{"price":"1.5","time":null}"#,
+            r#"{"price":"1.5","time":"yesterday"}"#,
+            r#"{"price":"1.5","time":-5}"#,
+            r#"{"price":"1.5","time":"1.5"}"#,
+            r#"{"price":"1.5","time":["1"]}"#,
+        ] {
+            let result = query_wasm_oracle("price", "time", response);
+            assert_eq!(reason(result), "missing or invalid timestamp");
+        }
+    }
+
+    #[test]
+    fn resolution_source_selects_the_adapter() {
+        assert_eq!(
+            OracleSource::from_str("https://example.com/rules").unwrap(),
+            OracleSource::ClpFeed
+        );
+        assert_eq!(
+            OracleSource::from_str("fixed:1.5").unwrap(),
+            OracleSource::Fixed {
+                price: Decimal::from_str("1.5").unwrap()
+            }
+        );
+        assert_eq!(
+            OracleSource::from_str(r#"wasm:data.price:data.time:{"price":{"denom":"{asset}"}}"#)
+                .unwrap(),
+            OracleSource::WasmQuery(WasmQuerySource {
+                query: r#"{"price":{"denom":"{asset}"}}"#.to_string(),
+                price_path: "data.price".to_string(),
+                timestamp_path: "data.time".to_string(),
+            })
+        );
+
+        for source in ["fixed:0", "fixed:abc", "wasm:price", "wasm::time:{}"] {
+            assert!(matches!(
+                OracleSource::from_str(source),
+                Err(ContractError::InvalidConfig { .. })
+            ));
+        }
+    }
+}
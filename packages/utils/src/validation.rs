@@ -7,6 +7,22 @@ pub fn validate_address(addr: &str) -> Result<Addr, ContractError> {
     Ok(Addr::unchecked(addr))
 }
 
+/// Whether `subunit` is a valid Coreum smart token subunit: `^[a-z][a-z0-9/:._]{0,50}$`
+pub fn is_valid_subunit(subunit: &str) -> bool {
+    let mut chars = subunit.chars();
+    subunit.len() <= 51
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "/:._".contains(c))
+}
+
+/// Whether `symbol` is a valid Coreum smart token symbol: `^[a-zA-Z][a-zA-Z0-9/:._-]{0,127}$`
+pub fn is_valid_symbol(symbol: &str) -> bool {
+    let mut chars = symbol.chars();
+    symbol.len() <= 128
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c))
+}
+
 /// Validates that funds match expected denom and amount
 pub fn validate_funds(
     info: &MessageInfo,
@@ -51,4 +67,31 @@ pub fn extract_denom_funds(
     })?;
 
     Ok(amount_128)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subunit_follows_coreum_rules() {
+        assert!(is_valid_subunit("truthyes_market_1"));
+        assert!(is_valid_subunit("truth60000_to_65000.5_m"));
+        assert!(!is_valid_subunit("truthyes!_market_1"));
+        assert!(!is_valid_subunit("truthyes-market"));
+        assert!(!is_valid_subunit("1truth"));
+        assert!(!is_valid_subunit(""));
+        assert!(is_valid_subunit(&"a".repeat(51)));
+        assert!(!is_valid_subunit(&"a".repeat(52)));
+    }
+
+    #[test]
+    fn symbol_follows_coreum_rules() {
+        assert!(is_valid_symbol("TMYesmarket-1"));
+        assert!(!is_valid_symbol("TMÉquipemarket1"));
+        assert!(!is_valid_symbol("TM?market1"));
+        assert!(!is_valid_symbol("9TM"));
+        assert!(is_valid_symbol(&"A".repeat(128)));
+        assert!(!is_valid_symbol(&"A".repeat(129)));
+    }
+}